@@ -0,0 +1,75 @@
+//! Spawns thousands of parcels in a headless round, letting them fall off the warehouse to be
+//! pooled and reused, and reports the spawn rate and how many parcel entities were created.
+//!
+//! `cargo bench --bench parcel_pool`
+
+use std::time::Instant;
+
+use bevy::prelude::*;
+use nordicnest_bf::*;
+use rand::Rng;
+
+/// Parcels spawned by the benchmark every update.
+const PARCELS_PER_UPDATE: usize = 10;
+const UPDATES: usize = 600;
+
+fn spawn_stress_parcels(
+    mut spawner: ResMut<ParcelSpawner>,
+    mut rng: ResMut<GameRng>,
+    mut parcels: ParcelCommands,
+) {
+    for _ in 0..PARCELS_PER_UPDATE {
+        // next to the warehouse so they fall straight into the void
+        let position = Vec3::new(
+            config::GROUND_SIZE * 2.0,
+            rng.0.gen_range(0.0..config::PARCEL_SPAWN_Y),
+            rng.0.gen_range(-config::GROUND_SIZE..config::GROUND_SIZE),
+        );
+        let kind = ParcelKind::ALL[rng.0.gen_range(0..ParcelKind::ALL.len())];
+
+        parcels.spawn(
+            &mut spawner,
+            position,
+            Vec3::NEG_Y * 20.0,
+            AgentServiceCode::DHL,
+            kind,
+        );
+    }
+}
+
+fn main() {
+    let mut app = App::new();
+    setup_headless_app(&mut app);
+    app.insert_resource(GameRng::from_seed(1));
+    // Zen rounds never end on their own
+    app.insert_resource(GameMode::Zen);
+    app.add_system_set(SystemSet::on_update(GameState::Ready).with_system(spawn_stress_parcels));
+
+    // enters the round
+    app.update();
+
+    let start = Instant::now();
+    let mut most_entities = 0;
+    for _ in 0..UPDATES {
+        app.update();
+
+        let entities = app
+            .world
+            .query_filtered::<(), With<ParcelKind>>()
+            .iter(&app.world)
+            .count();
+        most_entities = most_entities.max(entities);
+    }
+    let elapsed = start.elapsed();
+
+    let spawned = app.world.resource::<ParcelSpawner>().count;
+    let lost = app.world.resource::<RoundStats>().lost;
+
+    println!("spawned {spawned} parcels over {UPDATES} updates in {elapsed:.2?}");
+    println!(
+        "{:.1} updates/s, {:.0} spawns/s",
+        UPDATES as f64 / elapsed.as_secs_f64(),
+        spawned as f64 / elapsed.as_secs_f64()
+    );
+    println!("{lost} parcels pooled after falling off, at most {most_entities} parcel entities");
+}
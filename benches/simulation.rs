@@ -0,0 +1,270 @@
+//! Measures the parcel simulation in headless rounds holding 100, 1k and 10k parcels and writes
+//! the results as JSON for regression tracking.
+//!
+//! The fixed steps run their systems one after another, so the measured ones are timed where
+//! the schedule runs them, once every update.
+//!
+//! `cargo bench --bench simulation`, the JSON goes to `target/simulation_bench.json` unless
+//! `SIMULATION_BENCH_JSON` points elsewhere.
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use bevy::{
+    ecs::schedule::{ParallelSystemExecutor, SystemContainer},
+    prelude::*,
+};
+use bevy_rapier3d::plugin::{systems::step_simulation, NoUserData, PhysicsStages};
+use nordicnest_bf::*;
+use rand::Rng;
+use serde::Serialize;
+
+const PARCEL_COUNTS: [usize; 3] = [100, 1_000, 10_000];
+/// Updates letting the parcels settle before measuring.
+const WARMUP_UPDATES: usize = 30;
+const SAMPLES: usize = 50;
+/// Distance between parcels when they are stacked into the warehouse.
+const PARCEL_SPACING: f32 = 1.2;
+/// Space kept free along the edges of the floor.
+const FLOOR_PADDING: f32 = 1.0;
+
+#[derive(Serialize)]
+struct Measurement {
+    parcels: usize,
+    /// Parcels still in the warehouse after the samples, the others fell off.
+    live_parcels: usize,
+    system: &'static str,
+    samples: usize,
+    mean_us: f64,
+    median_us: f64,
+    min_us: f64,
+    max_us: f64,
+    std_dev_us: f64,
+}
+
+impl Measurement {
+    fn new(
+        parcels: usize,
+        live_parcels: usize,
+        system: &'static str,
+        mut samples: Vec<Duration>,
+    ) -> Self {
+        samples.sort();
+        let micros: Vec<f64> = samples
+            .iter()
+            .map(|sample| sample.as_secs_f64() * 1e6)
+            .collect();
+        let mean = micros.iter().sum::<f64>() / micros.len() as f64;
+        let variance =
+            micros.iter().map(|us| (us - mean).powi(2)).sum::<f64>() / micros.len() as f64;
+
+        Self {
+            parcels,
+            live_parcels,
+            system,
+            samples: micros.len(),
+            mean_us: mean,
+            median_us: micros[micros.len() / 2],
+            min_us: micros[0],
+            max_us: micros[micros.len() - 1],
+            std_dev_us: variance.sqrt(),
+        }
+    }
+}
+
+const CODES: [AgentServiceCode; 4] = [
+    AgentServiceCode::PostNord,
+    AgentServiceCode::DHL,
+    AgentServiceCode::Bring,
+    AgentServiceCode::Budbee,
+];
+
+/// Run time of the measured systems in the current update, by system name.
+#[derive(Clone, Default)]
+struct Timings(Arc<Mutex<HashMap<Cow<'static, str>, Duration>>>);
+
+/// Runs the systems of a stage one after another like Bevy's single-threaded executor and
+/// times the ones named in `measured`.
+struct TimingExecutor {
+    measured: Vec<Cow<'static, str>>,
+    timings: Timings,
+}
+
+impl ParallelSystemExecutor for TimingExecutor {
+    fn rebuild_cached_data(&mut self, _: &[SystemContainer]) {}
+
+    fn run_systems(&mut self, systems: &mut [SystemContainer], world: &mut World) {
+        for system in systems {
+            if !system.should_run() {
+                continue;
+            }
+
+            let start = Instant::now();
+            system.system_mut().run((), world);
+            let elapsed = start.elapsed();
+
+            let name = system.name();
+            if self.measured.contains(&name) {
+                *self.timings.0.lock().unwrap().entry(name).or_default() += elapsed;
+            }
+        }
+    }
+}
+
+/// Fills the floor with `count` parcels in layers, from the floor upwards.
+fn spawn_parcels(app: &mut App, count: usize) {
+    let half_width = config::GROUND_SIZE / 2.0 - FLOOR_PADDING;
+    let per_row = (2.0 * half_width / PARCEL_SPACING) as usize + 1;
+
+    let mut spawn = IntoSystem::into_system(
+        move |mut spawner: ResMut<ParcelSpawner>,
+              mut rng: ResMut<GameRng>,
+              mut parcels: ParcelCommands| {
+            for i in 0..count {
+                let (x, z, y) = (i % per_row, (i / per_row) % per_row, i / per_row.pow(2));
+                let position = Vec3::new(
+                    x as f32 * PARCEL_SPACING - half_width,
+                    (y as f32 + 1.0) * PARCEL_SPACING,
+                    z as f32 * PARCEL_SPACING - half_width,
+                );
+                let code = CODES[rng.0.gen_range(0..CODES.len())];
+
+                parcels.spawn(
+                    &mut spawner,
+                    position,
+                    Vec3::ZERO,
+                    code,
+                    ParcelKind::Standard,
+                );
+            }
+        },
+    );
+    spawn.initialize(&mut app.world);
+    spawn.run((), &mut app.world);
+    spawn.apply_buffers(&mut app.world);
+}
+
+fn bench(parcels: usize) -> Vec<Measurement> {
+    let measured: [(&'static str, Cow<'static, str>); 4] = [
+        (
+            "parcel_awarness",
+            IntoSystem::into_system(parcel_awarness).name(),
+        ),
+        (
+            "collect_parcels",
+            IntoSystem::into_system(collect_parcels).name(),
+        ),
+        (
+            "rapier_step",
+            IntoSystem::into_system(step_simulation::<NoUserData>).name(),
+        ),
+        (
+            "despawn_out_of_bounds",
+            IntoSystem::into_system(despawn_out_of_bounds).name(),
+        ),
+    ];
+
+    let mut app = App::new();
+    setup_headless_app(&mut app);
+    app.insert_resource(GameRng::from_seed(1));
+    // Zen rounds never end on their own
+    app.insert_resource(GameMode::Zen);
+
+    let timings = Timings::default();
+    let executor = || TimingExecutor {
+        measured: measured.iter().map(|(_, name)| name.clone()).collect(),
+        timings: timings.clone(),
+    };
+    app.schedule
+        .stage(FixedUpdateStage, |schedule: &mut Schedule| {
+            for stage in [
+                FixedStage::PreUpdate,
+                FixedStage::Update,
+                FixedStage::PostUpdate,
+            ] {
+                schedule.stage(stage, |stage: &mut SystemStage| {
+                    stage.set_executor(Box::new(executor()));
+                    stage
+                });
+            }
+            for stage in [
+                PhysicsStages::SyncBackend,
+                PhysicsStages::StepSimulation,
+                PhysicsStages::Writeback,
+            ] {
+                schedule.stage(stage, |stage: &mut SystemStage| {
+                    stage.set_executor(Box::new(executor()));
+                    stage
+                });
+            }
+            schedule
+        });
+
+    // enters the round
+    app.update();
+    spawn_parcels(&mut app, parcels);
+    for _ in 0..WARMUP_UPDATES {
+        app.update();
+    }
+
+    let mut frames = Vec::with_capacity(SAMPLES);
+    let mut samples = vec![Vec::with_capacity(SAMPLES); measured.len()];
+    for _ in 0..SAMPLES {
+        timings.0.lock().unwrap().clear();
+
+        let start = Instant::now();
+        app.update();
+        frames.push(start.elapsed());
+
+        let timings = timings.0.lock().unwrap();
+        for ((_, name), samples) in measured.iter().zip(&mut samples) {
+            if let Some(elapsed) = timings.get(name) {
+                samples.push(*elapsed);
+            }
+        }
+    }
+
+    let live_parcels = app
+        .world
+        .query_filtered::<(), With<Parcel>>()
+        .iter(&app.world)
+        .count();
+
+    let mut measurements = vec![Measurement::new(parcels, live_parcels, "frame", frames)];
+    measurements.extend(
+        measured
+            .iter()
+            .zip(samples)
+            .filter(|(_, samples)| !samples.is_empty())
+            .map(|((system, _), samples)| Measurement::new(parcels, live_parcels, system, samples)),
+    );
+    measurements
+}
+
+fn main() {
+    let mut measurements = Vec::new();
+    for parcels in PARCEL_COUNTS {
+        for measurement in bench(parcels) {
+            println!(
+                "{:>6} parcels ({:>6} live) {:<22} {:>10.1}us median {:>10.1}us mean ±{:.1}us",
+                measurement.parcels,
+                measurement.live_parcels,
+                measurement.system,
+                measurement.median_us,
+                measurement.mean_us,
+                measurement.std_dev_us,
+            );
+            measurements.push(measurement);
+        }
+    }
+
+    let path = std::env::var("SIMULATION_BENCH_JSON")
+        .unwrap_or_else(|_| "target/simulation_bench.json".to_string());
+    let json = serde_json::to_string_pretty(&measurements).unwrap();
+    std::fs::write(&path, json).unwrap_or_else(|err| panic!("could not write {path}: {err}"));
+    println!("results written to {path}");
+}
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    collect_parcels, on_fixed_update, prelude::*, storage, update_score, AgentServiceCode,
+    FixedAppExt, FontAssets, ParcelDelivered, RoundStats, Score, ScoreEvent,
+};
+
+/// Tracks achievement progress across sessions and announces unlocks, only added to the
+/// windowed game so bots don't unlock anything.
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(AchievementProgress::load());
+        app.add_event::<AchievementUnlocked>();
+        app.add_fixed_system_set(
+            on_fixed_update(GameState::Ready)
+                .with_system(track_deliveries.after(collect_parcels))
+                .with_system(track_score.after(update_score)),
+        )
+        .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(finish_round))
+        .add_system(show_toasts);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Achievement {
+    DhlCourier,
+    HighScorer,
+    #[serde(alias = "SteadyHands")]
+    CarefulSorter,
+    LongShot,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 4] = [
+        Achievement::DhlCourier,
+        Achievement::HighScorer,
+        Achievement::CarefulSorter,
+        Achievement::LongShot,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Achievement::DhlCourier => "DHL Courier",
+            Achievement::HighScorer => "High Scorer",
+            Achievement::CarefulSorter => "Careful Sorter",
+            Achievement::LongShot => "Long Shot",
+        }
+    }
+
+    pub fn description(&self) -> String {
+        match self {
+            Achievement::DhlCourier => format!("Deliver {} DHL parcels", self.goal()),
+            Achievement::HighScorer => format!("Score {} in one round", self.goal()),
+            Achievement::CarefulSorter => format!(
+                "Sort {} parcels in a round without losing one",
+                config::CAREFUL_SORTER_DELIVERIES
+            ),
+            Achievement::LongShot => format!(
+                "Land a throw in the right area from {}m",
+                config::LONG_SHOT_DISTANCE
+            ),
+        }
+    }
+
+    /// Progress needed to unlock.
+    pub fn goal(&self) -> u32 {
+        match self {
+            Achievement::DhlCourier => 50,
+            Achievement::HighScorer => 30,
+            Achievement::CarefulSorter | Achievement::LongShot => 1,
+        }
+    }
+}
+
+/// Sent once when an achievement reaches its goal.
+pub struct AchievementUnlocked(pub Achievement);
+
+/// Progress of every achievement, kept in the data directory.
+#[derive(Resource, Default, Clone, Debug, Serialize, Deserialize)]
+pub struct AchievementProgress {
+    pub progress: HashMap<Achievement, u32>,
+}
+
+impl AchievementProgress {
+    const FILE: &'static str = "achievements.json";
+
+    pub fn load() -> Self {
+        storage::load(Self::FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::FILE, self);
+    }
+
+    pub fn get(&self, achievement: Achievement) -> u32 {
+        self.progress.get(&achievement).copied().unwrap_or(0)
+    }
+
+    pub fn is_unlocked(&self, achievement: Achievement) -> bool {
+        self.get(achievement) >= achievement.goal()
+    }
+
+    /// Raises the progress of `achievement` to at least `value`, returns whether that unlocked it.
+    pub fn reach(&mut self, achievement: Achievement, value: u32) -> bool {
+        let was_unlocked = self.is_unlocked(achievement);
+        let progress = self.progress.entry(achievement).or_default();
+        *progress = (*progress).max(value).min(achievement.goal());
+
+        !was_unlocked && self.is_unlocked(achievement)
+    }
+
+    fn advance(
+        &mut self,
+        achievement: Achievement,
+        value: u32,
+        unlocked: &mut EventWriter<AchievementUnlocked>,
+    ) {
+        if self.reach(achievement, value) {
+            unlocked.send(AchievementUnlocked(achievement));
+            self.save();
+        }
+    }
+}
+
+fn track_deliveries(
+    mut progress: ResMut<AchievementProgress>,
+    mut delivered: EventReader<ParcelDelivered>,
+    mut unlocked: EventWriter<AchievementUnlocked>,
+) {
+    for event in delivered.iter().filter(|event| event.is_correct()) {
+        if event.code == AgentServiceCode::DHL {
+            let delivered = progress.get(Achievement::DhlCourier) + 1;
+            progress.advance(Achievement::DhlCourier, delivered, &mut unlocked);
+        }
+
+        if event
+            .throw_distance
+            .is_some_and(|distance| distance >= config::LONG_SHOT_DISTANCE)
+        {
+            progress.advance(Achievement::LongShot, 1, &mut unlocked);
+        }
+    }
+}
+
+fn track_score(
+    score: Res<Score>,
+    mut progress: ResMut<AchievementProgress>,
+    mut score_events: EventReader<ScoreEvent>,
+    mut unlocked: EventWriter<AchievementUnlocked>,
+) {
+    if score_events.iter().count() == 0 {
+        return;
+    }
+
+    let round_score = score.score.max(0) as u32;
+    progress.advance(Achievement::HighScorer, round_score, &mut unlocked);
+}
+
+fn finish_round(
+    stats: Res<RoundStats>,
+    mut progress: ResMut<AchievementProgress>,
+    mut unlocked: EventWriter<AchievementUnlocked>,
+) {
+    if stats.lost == 0 && stats.correct >= config::CAREFUL_SORTER_DELIVERIES {
+        progress.advance(Achievement::CarefulSorter, 1, &mut unlocked);
+    }
+
+    // keeps the progress of achievements that didn't unlock this round
+    progress.save();
+}
+
+/// Pops up in the corner for a few seconds when an achievement unlocks.
+#[derive(Component)]
+pub struct AchievementToast {
+    pub timer: Timer,
+}
+
+fn show_toasts(
+    mut commands: Commands,
+    time: Res<Time>,
+    font_assets: Option<Res<FontAssets>>,
+    mut unlocked: EventReader<AchievementUnlocked>,
+    mut toasts: Query<(Entity, &mut AchievementToast)>,
+) {
+    for (entity, mut toast) in &mut toasts {
+        toast.timer.tick(time.delta());
+        if toast.timer.finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+
+    let Some(font_assets) = font_assets else {
+        return;
+    };
+
+    // stacks new toasts above the ones still showing
+    for (index, AchievementUnlocked(achievement)) in (toasts.iter().len()..).zip(unlocked.iter()) {
+        commands.spawn((
+            TextBundle::from_sections([
+                TextSection::new(
+                    format!("Achievement unlocked: {}\n", achievement.name()),
+                    TextStyle {
+                        font: font_assets.montserrat.clone(),
+                        font_size: 26.0,
+                        color: Color::rgb(1.0, 0.85, 0.1),
+                    },
+                ),
+                TextSection::new(
+                    achievement.description(),
+                    TextStyle {
+                        font: font_assets.montserrat.clone(),
+                        font_size: 18.0,
+                        color: Color::rgb(0.9, 0.9, 0.9),
+                    },
+                ),
+            ])
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    bottom: Val::Px(70.0 + 60.0 * index as f32),
+                    right: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            }),
+            AchievementToast {
+                timer: Timer::from_seconds(4.0, TimerMode::Once),
+            },
+        ));
+    }
+}
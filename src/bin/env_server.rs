@@ -0,0 +1,12 @@
+//! Serves the training environment over TCP, see [`nordicnest_bf::serve_env`].
+//!
+//! Usage: `env_server [address]`, the address defaults to `127.0.0.1:7878`.
+
+fn main() -> std::io::Result<()> {
+    let addr = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "127.0.0.1:7878".to_string());
+
+    println!("serving env on {addr}");
+    nordicnest_bf::serve_env(addr)
+}
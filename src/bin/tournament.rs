@@ -0,0 +1,23 @@
+//! Plays headless rounds with a bot across a range of seeds and prints tables of how they went,
+//! see [`nordicnest_bf::Tournament`].
+//!
+//! Usage: `tournament [OPTIONS]`, `tournament --help` lists the options.
+
+use nordicnest_bf::{CliError, Tournament};
+
+fn main() {
+    let tournament = match Tournament::parse(std::env::args().skip(1)) {
+        Ok(tournament) => tournament,
+        Err(CliError::Help) => {
+            print!("{}", nordicnest_bf::TOURNAMENT_HELP);
+            return;
+        }
+        Err(err) => {
+            eprintln!("error: {err}\n\nRun with --help to see the options.");
+            std::process::exit(2);
+        }
+    };
+
+    let results = tournament.run();
+    print!("{}", tournament.report(&results));
+}
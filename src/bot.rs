@@ -0,0 +1,227 @@
+use bevy_spatial::SpatialAccess;
+
+use crate::{
+    prelude::*, AgentServiceCode, ClosestParcel, Despawn, Parcel, ParcelStack, ParcelStackEntry,
+    ParcelsSpatialTree, Picked, Player, PlayerActions, ShippingArea, WritePlayerActions,
+};
+
+/// Drives the player with a [`WorkerBrain`] instead of keyboard & mouse.
+pub struct BotPlugin<B: WorkerBrain + Clone>(pub B);
+
+impl<B: WorkerBrain + Clone> Plugin for BotPlugin<B> {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Bot(self.0.clone()));
+        app.add_system_set(
+            SystemSet::on_update(GameState::Ready)
+                .with_system(drive_bot::<B>.label(WritePlayerActions)),
+        );
+    }
+}
+
+/// Decides what a computer controlled worker does each frame.
+pub trait WorkerBrain: Send + Sync + 'static {
+    fn act(&mut self, observation: &WorkerObservation) -> PlayerActions;
+}
+
+#[derive(Resource)]
+pub struct Bot<B: WorkerBrain>(pub B);
+
+/// Everything a [`WorkerBrain`] gets to see of the warehouse.
+#[derive(Debug, Clone)]
+pub struct WorkerObservation {
+    pub position: Vec3,
+    /// Carriers of the stacked parcels, bottom first.
+    pub stack: Vec<AgentServiceCode>,
+    pub stack_capacity: usize,
+    /// Parcel that would be stacked by a pickup action.
+    pub closest_parcel: Option<Entity>,
+    /// Free parcels within [`config::BOT_VIEW_RADIUS`].
+    pub parcels: Vec<ObservedParcel>,
+    pub shipping_areas: Vec<ObservedShippingArea>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ObservedParcel {
+    pub entity: Entity,
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub code: AgentServiceCode,
+}
+
+#[derive(Debug, Clone)]
+pub struct ObservedShippingArea {
+    pub position: Vec3,
+    pub code: AgentServiceCode,
+}
+
+/// Brains to pick by name, e.g. for tournaments.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BotStrategy {
+    Greedy,
+    Stacking,
+}
+
+impl BotStrategy {
+    pub const ALL: [BotStrategy; 2] = [BotStrategy::Greedy, BotStrategy::Stacking];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BotStrategy::Greedy => "greedy",
+            BotStrategy::Stacking => "stacking",
+        }
+    }
+
+    /// Adds the [`BotPlugin`] with the brain of the strategy.
+    pub fn add_to(&self, app: &mut App) {
+        match self {
+            BotStrategy::Greedy => app.add_plugin(BotPlugin(GreedyBrain)),
+            BotStrategy::Stacking => app.add_plugin(BotPlugin(StackingBrain::default())),
+        };
+    }
+}
+
+// throws carry roughly 1.6 times the aimed distance, aim short to land inside the area
+const THROW_LEAD: f32 = 0.6;
+// ignore falling and thrown parcels, chasing them only ends up re-stacking our own throws
+const RESTING_SPEED: f32 = 1.0;
+
+/// Walks to the closest resting parcel, stacks it and throws it at the area of its carrier.
+#[derive(Debug, Clone, Default)]
+pub struct GreedyBrain;
+
+impl WorkerBrain for GreedyBrain {
+    fn act(&mut self, observation: &WorkerObservation) -> PlayerActions {
+        if !observation.stack.is_empty() {
+            return throw_top(observation);
+        }
+
+        fetch(observation, closest_resting_parcel(observation))
+    }
+}
+
+/// Stacks parcels until the stack is full or none are left in view, then throws them one after
+/// another. Fewer walks to the parcels, but each one is heavier.
+#[derive(Debug, Clone, Default)]
+pub struct StackingBrain {
+    unloading: bool,
+}
+
+impl WorkerBrain for StackingBrain {
+    fn act(&mut self, observation: &WorkerObservation) -> PlayerActions {
+        let closest = closest_resting_parcel(observation);
+
+        if observation.stack.is_empty() {
+            self.unloading = false;
+        } else if observation.stack.len() >= observation.stack_capacity || closest.is_none() {
+            self.unloading = true;
+        }
+
+        if self.unloading {
+            throw_top(observation)
+        } else {
+            fetch(observation, closest)
+        }
+    }
+}
+
+/// Throws the top parcel at the area of its carrier.
+fn throw_top(observation: &WorkerObservation) -> PlayerActions {
+    let aim = observation.stack.last().and_then(|code| {
+        observation
+            .shipping_areas
+            .iter()
+            .find(|area| area.code == *code)
+            .map(|area| observation.position + (area.position - observation.position) * THROW_LEAD)
+    });
+
+    PlayerActions {
+        throw: true,
+        aim,
+        ..default()
+    }
+}
+
+fn closest_resting_parcel(observation: &WorkerObservation) -> Option<&ObservedParcel> {
+    observation
+        .parcels
+        .iter()
+        .filter(|parcel| parcel.velocity.length() < RESTING_SPEED)
+        .min_by(|a, b| {
+            a.position
+                .distance(observation.position)
+                .total_cmp(&b.position.distance(observation.position))
+        })
+}
+
+/// Walks to `parcel` and stacks it once it's the closest one, stands still without a parcel.
+fn fetch(observation: &WorkerObservation, parcel: Option<&ObservedParcel>) -> PlayerActions {
+    if let Some(parcel) = parcel {
+        let movement = parcel.position - observation.position;
+        PlayerActions {
+            movement: Vec3::new(movement.x, 0.0, movement.z),
+            pickup: observation.closest_parcel == Some(parcel.entity),
+            ..default()
+        }
+    } else {
+        PlayerActions::default()
+    }
+}
+
+fn drive_bot<B: WorkerBrain>(
+    mut bot: ResMut<Bot<B>>,
+    mut actions: ResMut<PlayerActions>,
+    closest_parcel: Res<ClosestParcel>,
+    tree: Res<ParcelsSpatialTree>,
+    player: Query<&Transform, With<Player>>,
+    parcel_stack: Query<&ParcelStack>,
+    parcel_stack_entries: Query<&ParcelStackEntry>,
+    parcels: Query<&AgentServiceCode, (With<Parcel>, Without<Despawn>)>,
+    free_parcels: Query<
+        (&AgentServiceCode, &Velocity),
+        (With<Parcel>, Without<Picked>, Without<Despawn>),
+    >,
+    shipping_areas: Query<(&Transform, &AgentServiceCode), With<ShippingArea>>,
+) {
+    let position = player.single().translation;
+    let parcel_stack = parcel_stack.single();
+
+    let stack = parcel_stack
+        .parcels_entries
+        .iter()
+        .filter_map(|entry| parcel_stack_entries.get(*entry).ok()?.parcel)
+        .filter_map(|parcel| parcels.get(parcel).ok().copied())
+        .collect();
+
+    let parcels = tree
+        .within_distance(position, config::BOT_VIEW_RADIUS)
+        .into_iter()
+        .filter_map(|(position, entity)| {
+            free_parcels
+                .get(entity)
+                .ok()
+                .map(|(code, velocity)| ObservedParcel {
+                    entity,
+                    position,
+                    velocity: velocity.linvel,
+                    code: *code,
+                })
+        })
+        .collect();
+
+    let shipping_areas = shipping_areas
+        .iter()
+        .map(|(transform, code)| ObservedShippingArea {
+            position: transform.translation,
+            code: *code,
+        })
+        .collect();
+
+    actions.replace(bot.0.act(&WorkerObservation {
+        position,
+        stack,
+        stack_capacity: parcel_stack.capacity,
+        closest_parcel: closest_parcel.0,
+        parcels,
+        shipping_areas,
+    }));
+}
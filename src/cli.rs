@@ -0,0 +1,333 @@
+//! Options of the native game, given as flags or in a config file.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use ron::extensions::Extensions;
+use serde::Deserialize;
+
+use crate::{
+    prelude::*, BotPlugin, Difficulty, FixedSeed, GameMode, GameRng, GreedyBrain, Replay,
+    ReplayPlaybackPlugin, ReplayRecorderPlugin, SpawnTimeline,
+};
+
+pub const CLI_HELP: &str = "\
+Black Friday, sort the packages before the trucks leave.
+
+Usage: nordicnest_bf [OPTIONS]
+
+Options:
+      --seed <SEED>              Seed of the packages, counts up with every headless round
+      --mode <MODE>              timed, endless, zen or sudden-death
+      --difficulty <DIFFICULTY>  easy, normal or hard
+      --level <FILE>             Waves of packages in RON, like assets/waves/timeline.ron
+      --config <FILE>            Any of these options in RON, e.g. (seed: 7, fullscreen: true),
+                                 the flags take precedence
+      --width <PIXELS>           Width of the window
+      --height <PIXELS>          Height of the window
+      --fullscreen               Fills the screen instead of opening a window
+      --headless                 Simulates rounds played by the greedy bot without a window
+      --rounds <COUNT>           Rounds to simulate with --headless [default: 1]
+      --replay <FILE>            Plays back a recorded round with its seed, rules and level
+      --record <FILE>            Records the round to FILE, the last one if several are played
+  -h, --help                     Prints this help
+";
+
+/// Options as given, flags override the ones of the config file.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LaunchOptions {
+    pub seed: Option<u64>,
+    pub mode: Option<GameMode>,
+    pub difficulty: Option<Difficulty>,
+    pub level: Option<PathBuf>,
+    pub width: Option<f32>,
+    pub height: Option<f32>,
+    pub fullscreen: Option<bool>,
+    pub headless: Option<bool>,
+    pub rounds: Option<u32>,
+    pub replay: Option<PathBuf>,
+    pub record: Option<PathBuf>,
+}
+
+impl LaunchOptions {
+    /// Reads the options of the config file at `path`.
+    pub fn read(path: &Path) -> Result<Self, CliError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| invalid(format!("could not read {}: {err}", path.display())))?;
+
+        ron::Options::default()
+            .with_default_extension(Extensions::IMPLICIT_SOME)
+            .from_str(&contents)
+            .map_err(|err| invalid(format!("{} is not a config file: {err}", path.display())))
+    }
+
+    /// Options of `self`, those of `fallback` where `self` has none.
+    pub fn or(self, fallback: Self) -> Self {
+        Self {
+            seed: self.seed.or(fallback.seed),
+            mode: self.mode.or(fallback.mode),
+            difficulty: self.difficulty.or(fallback.difficulty),
+            level: self.level.or(fallback.level),
+            width: self.width.or(fallback.width),
+            height: self.height.or(fallback.height),
+            fullscreen: self.fullscreen.or(fallback.fullscreen),
+            headless: self.headless.or(fallback.headless),
+            rounds: self.rounds.or(fallback.rounds),
+            replay: self.replay.or(fallback.replay),
+            record: self.record.or(fallback.record),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CliError {
+    /// `--help` was given.
+    Help,
+    Invalid(String),
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Help => f.write_str(CLI_HELP),
+            CliError::Invalid(message) => f.write_str(message),
+        }
+    }
+}
+
+pub(crate) fn invalid(message: String) -> CliError {
+    CliError::Invalid(message)
+}
+
+/// Size and mode of the window of the windowed game.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq)]
+pub struct WindowOptions {
+    pub width: Option<f32>,
+    pub height: Option<f32>,
+    pub fullscreen: bool,
+}
+
+impl WindowOptions {
+    pub fn apply(&self, window: &mut Window) {
+        if self.fullscreen {
+            window.set_mode(WindowMode::BorderlessFullscreen);
+        } else if self.width.is_some() || self.height.is_some() {
+            window.set_resolution(
+                self.width.unwrap_or(window.requested_width()),
+                self.height.unwrap_or(window.requested_height()),
+            );
+        }
+    }
+}
+
+/// Checked options with their files read.
+#[derive(Debug)]
+pub struct Cli {
+    pub seed: Option<u64>,
+    pub mode: Option<GameMode>,
+    pub difficulty: Option<Difficulty>,
+    pub level: Option<SpawnTimeline>,
+    pub window: WindowOptions,
+    pub headless: bool,
+    pub rounds: u32,
+    pub replay: Option<Replay>,
+    pub record: Option<PathBuf>,
+}
+
+impl Cli {
+    /// Parses the arguments after the program name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, CliError> {
+        let mut flags = LaunchOptions::default();
+        let mut config = None;
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| invalid(format!("{arg} needs a value")))
+            };
+
+            match arg.as_str() {
+                "-h" | "--help" => return Err(CliError::Help),
+                "--seed" => flags.seed = Some(parse_number(&arg, &value()?)?),
+                "--mode" => flags.mode = Some(parse_mode(&value()?)?),
+                "--difficulty" => flags.difficulty = Some(parse_difficulty(&value()?)?),
+                "--level" => flags.level = Some(value()?.into()),
+                "--config" => config = Some(PathBuf::from(value()?)),
+                "--width" => flags.width = Some(parse_number(&arg, &value()?)?),
+                "--height" => flags.height = Some(parse_number(&arg, &value()?)?),
+                "--fullscreen" => flags.fullscreen = Some(true),
+                "--headless" => flags.headless = Some(true),
+                "--rounds" => flags.rounds = Some(parse_number(&arg, &value()?)?),
+                "--replay" => flags.replay = Some(value()?.into()),
+                "--record" => flags.record = Some(value()?.into()),
+                _ => return Err(invalid(format!("unexpected argument {arg}"))),
+            }
+        }
+
+        let options = match config {
+            Some(path) => flags.or(LaunchOptions::read(&path)?),
+            None => flags,
+        };
+
+        Self::from_options(options)
+    }
+
+    /// Checks that the options go together and reads the level and the replay.
+    pub fn from_options(options: LaunchOptions) -> Result<Self, CliError> {
+        let headless = options.headless.unwrap_or(false);
+        let fullscreen = options.fullscreen.unwrap_or(false);
+        let sized = options.width.is_some() || options.height.is_some();
+
+        if options.rounds.is_some() && !headless {
+            return Err(invalid("--rounds needs --headless".to_string()));
+        }
+        if options.rounds == Some(0) {
+            return Err(invalid("--rounds must be at least 1".to_string()));
+        }
+        if headless && (sized || fullscreen) {
+            return Err(invalid(
+                "--width, --height and --fullscreen don't apply to --headless".to_string(),
+            ));
+        }
+        if fullscreen && sized {
+            return Err(invalid(
+                "--width and --height don't apply to --fullscreen".to_string(),
+            ));
+        }
+        if [options.width, options.height]
+            .iter()
+            .flatten()
+            .any(|size| !(size.is_finite() && *size >= 1.0))
+        {
+            return Err(invalid("--width and --height must be positive".to_string()));
+        }
+
+        let rounds = options.rounds.unwrap_or(1);
+        if rounds > 1 && options.replay.is_some() {
+            return Err(invalid("--replay plays a single round".to_string()));
+        }
+
+        let replay = match &options.replay {
+            Some(path) => {
+                let given = [
+                    ("--seed", options.seed.is_some()),
+                    ("--mode", options.mode.is_some()),
+                    ("--difficulty", options.difficulty.is_some()),
+                    ("--level", options.level.is_some()),
+                    ("--record", options.record.is_some()),
+                ];
+                if let Some((flag, _)) = given.iter().find(|(_, given)| *given) {
+                    return Err(invalid(format!(
+                        "{flag} can't be combined with --replay, the round is played as recorded"
+                    )));
+                }
+
+                Some(Replay::read(path).map_err(invalid)?)
+            }
+            None => None,
+        };
+
+        let level = options.level.as_deref().map(read_level).transpose()?;
+
+        Ok(Self {
+            seed: options.seed,
+            mode: options.mode,
+            difficulty: options.difficulty,
+            level,
+            window: WindowOptions {
+                width: options.width,
+                height: options.height,
+                fullscreen,
+            },
+            headless,
+            rounds,
+            replay,
+            record: options.record,
+        })
+    }
+
+    /// Sets up `app` for the given round, counting from `0`, after the game was added to it.
+    pub fn configure(&self, app: &mut App, round: u32) {
+        if let Some(replay) = &self.replay {
+            app.add_plugin(ReplayPlaybackPlugin(replay.clone()));
+            return;
+        }
+
+        if let Some(seed) = self.seed {
+            let seed = seed.wrapping_add(round as u64);
+            app.insert_resource(FixedSeed(Some(seed)));
+            app.insert_resource(GameRng::from_seed(seed));
+        }
+        if let Some(mode) = self.mode {
+            app.insert_resource(mode);
+        }
+        if let Some(difficulty) = self.difficulty {
+            app.insert_resource(difficulty);
+        }
+        if let Some(level) = &self.level {
+            app.insert_resource(level.clone());
+        }
+        // only the last round is kept
+        if let Some(path) = self.record.as_ref().filter(|_| round + 1 == self.rounds) {
+            app.add_plugin(ReplayRecorderPlugin(path.clone()));
+        }
+        if self.headless {
+            app.add_plugin(BotPlugin(GreedyBrain));
+        }
+    }
+}
+
+/// Reads the waves of a `--level` file.
+pub(crate) fn read_level(path: &Path) -> Result<SpawnTimeline, CliError> {
+    let contents = std::fs::read_to_string(path)
+        .map_err(|err| invalid(format!("could not read {}: {err}", path.display())))?;
+
+    let level: SpawnTimeline = ron::from_str(&contents)
+        .map_err(|err| invalid(format!("{} is not a level: {err}", path.display())))?;
+    level
+        .validate()
+        .map_err(|err| invalid(format!("{} is not a level: {err}", path.display())))?;
+
+    Ok(level)
+}
+
+pub(crate) fn parse_number<T: FromStr>(flag: &str, value: &str) -> Result<T, CliError> {
+    value
+        .parse()
+        .map_err(|_| invalid(format!("{flag} expects a number, got {value}")))
+}
+
+pub(crate) fn parse_mode(value: &str) -> Result<GameMode, CliError> {
+    let name = |mode: &GameMode| mode.name().to_lowercase().replace(' ', "-");
+
+    GameMode::ALL
+        .into_iter()
+        .find(|mode| name(mode) == value)
+        .ok_or_else(|| {
+            let names: Vec<_> = GameMode::ALL.iter().map(name).collect();
+            invalid(format!(
+                "unknown mode {value}, expected one of {}",
+                names.join(", ")
+            ))
+        })
+}
+
+pub(crate) fn parse_difficulty(value: &str) -> Result<Difficulty, CliError> {
+    let name = |difficulty: &Difficulty| format!("{difficulty:?}").to_lowercase();
+
+    Difficulty::ALL
+        .into_iter()
+        .find(|difficulty| name(difficulty) == value)
+        .ok_or_else(|| {
+            let names: Vec<_> = Difficulty::ALL.iter().map(name).collect();
+            invalid(format!(
+                "unknown difficulty {value}, expected one of {}",
+                names.join(", ")
+            ))
+        })
+}
@@ -0,0 +1,162 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{prelude::*, storage, Difficulty, GameMode, GameRng, Mutator, Mutators, RoundStats};
+
+/// Rules of the day, derived from the date alone so every player gets the same parcels.
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DailyChallenge {
+    /// UTC date as `YYYY-MM-DD`.
+    pub date: String,
+    pub seed: u64,
+    pub difficulty: Difficulty,
+    pub mode: GameMode,
+    pub mutators: Mutators,
+}
+
+impl DailyChallenge {
+    /// Challenge of the current UTC day.
+    pub fn today() -> Self {
+        Self::for_day(days_since_epoch())
+    }
+
+    /// Challenge of the day `days` after 1970-01-01.
+    pub fn for_day(days: i64) -> Self {
+        let seed = split_mix(days as u64 ^ 0xB1AC_F71D_A700_0000);
+        // Zen isn't scored, it makes no challenge
+        let modes = [GameMode::Timed, GameMode::Endless, GameMode::SuddenDeath];
+        let difficulties = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+        // every mutator has a one in four chance to be part of the day
+        let mutators = Mutator::ALL
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| (seed >> (16 + 2 * i)) & 3 == 0)
+            .map(|(_, mutator)| mutator)
+            .collect();
+
+        Self {
+            date: civil_date(days),
+            seed,
+            difficulty: difficulties[(seed % 3) as usize],
+            mode: modes[((seed >> 8) % 3) as usize],
+            mutators: Mutators(mutators),
+        }
+    }
+
+    /// Rng of the round, the same one for everyone playing this challenge.
+    pub fn rng(&self) -> GameRng {
+        GameRng::from_seed(self.seed)
+    }
+
+    /// Name of the exported [`summary`](Self::summary).
+    pub fn summary_file(&self) -> String {
+        format!("daily-{}.txt", self.date)
+    }
+
+    /// Text to paste to the team chat.
+    pub fn summary(&self, result: &DailyResult) -> String {
+        format!(
+            "Black Friday daily {}\n{} on {:?}\nMutators: {}\nScore {}\n{} sorted, {} wrong, {} lost in {:.0}s",
+            self.date,
+            self.mode.name(),
+            self.difficulty,
+            self.mutators.describe(),
+            result.score,
+            result.correct,
+            result.wrong,
+            result.lost,
+            result.seconds,
+        )
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DailyResult {
+    pub score: i32,
+    pub correct: u32,
+    pub wrong: u32,
+    pub lost: u32,
+    pub seconds: f32,
+}
+
+impl DailyResult {
+    pub fn new(score: i32, stats: &RoundStats) -> Self {
+        Self {
+            score,
+            correct: stats.correct,
+            wrong: stats.wrong,
+            lost: stats.lost,
+            seconds: stats.elapsed,
+        }
+    }
+}
+
+/// Best result of every daily challenge played, kept in the data directory.
+#[derive(Resource, Default, Clone, Debug, Serialize, Deserialize)]
+pub struct DailyResults {
+    pub best: BTreeMap<String, DailyResult>,
+}
+
+impl DailyResults {
+    const FILE: &'static str = "daily_results.json";
+
+    pub fn load() -> Self {
+        storage::load(Self::FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::FILE, self);
+    }
+
+    /// Keeps `result` if it beats the best one of `date`, returns whether it did.
+    pub fn record(&mut self, date: &str, result: DailyResult) -> bool {
+        match self.best.get(date) {
+            Some(best) if best.score >= result.score => false,
+            _ => {
+                self.best.insert(date.to_string(), result);
+                true
+            }
+        }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn days_since_epoch() -> i64 {
+    let seconds = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs());
+
+    (seconds / 86_400) as i64
+}
+
+#[cfg(target_arch = "wasm32")]
+fn days_since_epoch() -> i64 {
+    (js_sys::Date::now() / 86_400_000.0).floor() as i64
+}
+
+/// `YYYY-MM-DD` of the day `days` after 1970-01-01 in the proleptic Gregorian calendar.
+pub fn civil_date(days: i64) -> String {
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Scrambles neighbouring days into unrelated seeds, stable across platforms and releases.
+fn split_mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
@@ -0,0 +1,124 @@
+use rand::{distributions::WeightedIndex, prelude::Distribution, Rng};
+use serde::{Deserialize, Serialize};
+
+use crate::{prelude::*, AgentServiceCode, ParcelKind};
+
+/// Keeps the [`DifficultyCurve`] of the selected [`Difficulty`] preset in place.
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Difficulty>();
+        app.insert_resource(Difficulty::default().curve());
+        app.add_system_set(
+            SystemSet::on_update(GameState::Ready).with_system(load_difficulty_curve),
+        );
+    }
+}
+
+/// Picks the difficulty preset with F1, F2 and F3 in the menu, only added to the windowed game.
+pub struct DifficultySelectPlugin;
+
+impl Plugin for DifficultySelectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_update(GameState::Menu).with_system(select_difficulty));
+    }
+}
+
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    pub fn curve(&self) -> DifficultyCurve {
+        let source = match self {
+            Difficulty::Easy => include_str!("../assets/difficulty/easy.ron"),
+            Difficulty::Normal => include_str!("../assets/difficulty/normal.ron"),
+            Difficulty::Hard => include_str!("../assets/difficulty/hard.ron"),
+        };
+
+        ron::from_str(source).expect("difficulty presets are valid")
+    }
+}
+
+/// How parcels arrive over the course of a round, authored in `assets/difficulty`.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct DifficultyCurve {
+    pub name: String,
+    /// Tiers ordered by the second they start at, the first one starts at `0.0`.
+    pub tiers: Vec<DifficultyTier>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct DifficultyTier {
+    pub name: String,
+    /// Seconds into the round this tier starts at.
+    pub from: f32,
+    /// Seconds between two spawns.
+    pub spawn_interval: f32,
+    /// Inclusive range of parcels dropped at each spawn.
+    pub burst: (u32, u32),
+    /// Relative weights of the carriers of spawned parcels.
+    pub carriers: Vec<(AgentServiceCode, f32)>,
+    /// Relative weights of the kinds of spawned parcels.
+    pub kinds: Vec<(ParcelKind, f32)>,
+}
+
+impl DifficultyCurve {
+    /// Index and tier in effect `elapsed` seconds into the round.
+    pub fn tier_at(&self, elapsed: f32) -> (usize, &DifficultyTier) {
+        self.tiers
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, tier)| tier.from <= elapsed)
+            .unwrap_or((0, &self.tiers[0]))
+    }
+}
+
+impl DifficultyTier {
+    pub fn burst_size(&self, rng: &mut impl Rng) -> u32 {
+        rng.gen_range(self.burst.0..=self.burst.1.max(self.burst.0))
+    }
+
+    pub fn carrier(&self, rng: &mut impl Rng) -> AgentServiceCode {
+        pick_weighted(&self.carriers, rng).unwrap_or(AgentServiceCode::PostNord)
+    }
+
+    pub fn kind(&self, rng: &mut impl Rng) -> ParcelKind {
+        pick_weighted(&self.kinds, rng).unwrap_or(ParcelKind::Standard)
+    }
+}
+
+fn pick_weighted<T: Copy>(choices: &[(T, f32)], rng: &mut impl Rng) -> Option<T> {
+    let weights = WeightedIndex::new(choices.iter().map(|(_, weight)| *weight)).ok()?;
+    Some(choices[weights.sample(rng)].0)
+}
+
+fn load_difficulty_curve(mut commands: Commands, difficulty: Res<Difficulty>) {
+    if difficulty.is_changed() {
+        commands.insert_resource(difficulty.curve());
+    }
+}
+
+fn select_difficulty(keyboard_input: Res<Input<KeyCode>>, mut difficulty: ResMut<Difficulty>) {
+    let selected = if keyboard_input.just_pressed(KeyCode::F1) {
+        Difficulty::Easy
+    } else if keyboard_input.just_pressed(KeyCode::F2) {
+        Difficulty::Normal
+    } else if keyboard_input.just_pressed(KeyCode::F3) {
+        Difficulty::Hard
+    } else {
+        return;
+    };
+
+    if *difficulty != selected {
+        *difficulty = selected;
+    }
+}
@@ -0,0 +1,219 @@
+use bevy::ecs::event::ManualEventReader;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    prelude::*, setup_headless_app, AgentServiceCode, Dash, Despawn, GameRng, Parcel, ParcelStack,
+    ParcelStackEntry, Picked, Player, PlayerActions, Score, ScoreEvent, Stamina, TimeRemaining,
+};
+
+/// Step/reset interface over a headless round, meant for training agents.
+///
+/// Every [`WarehouseEnv::step`] applies one action for a single [`HEADLESS_TIMESTEP`] frame.
+///
+/// [`HEADLESS_TIMESTEP`]: crate::HEADLESS_TIMESTEP
+pub struct WarehouseEnv {
+    app: App,
+    score_events: ManualEventReader<ScoreEvent>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EnvAction {
+    /// Movement direction on the ground plane as `[x, z]`.
+    pub movement: [f32; 2],
+    pub pickup: bool,
+    /// Throws the top parcel of the stack towards this point on the ground.
+    pub throw: Option<[f32; 3]>,
+    #[serde(default)]
+    pub sprint: bool,
+    #[serde(default)]
+    pub dash: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvObservation {
+    pub player_position: [f32; 3],
+    /// Carriers of the stacked parcels, bottom first.
+    pub stack: Vec<AgentServiceCode>,
+    pub stack_capacity: usize,
+    pub parcels: Vec<EnvParcel>,
+    pub stamina: f32,
+    /// Seconds until the player can dash again.
+    pub dash_cooldown: f32,
+    pub score: i32,
+    pub time_remaining: f32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvParcel {
+    pub code: AgentServiceCode,
+    pub position: [f32; 3],
+    pub velocity: [f32; 3],
+    pub stacked: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvStep {
+    pub observation: EnvObservation,
+    /// Sum of the [`ScoreEvent`]s sent during the step.
+    pub reward: i32,
+    pub done: bool,
+}
+
+impl WarehouseEnv {
+    pub fn new(seed: u64) -> Self {
+        let mut env = Self {
+            app: App::new(),
+            score_events: ManualEventReader::default(),
+        };
+        env.reset(seed);
+        env
+    }
+
+    /// Starts a new round whose parcel sequence is derived from `seed`.
+    pub fn reset(&mut self, seed: u64) -> EnvObservation {
+        let mut app = App::new();
+        setup_headless_app(&mut app);
+        app.insert_resource(GameRng::from_seed(seed));
+        // runs the `on_enter` systems spawning the player and warehouse
+        app.update();
+
+        self.app = app;
+        self.score_events = ManualEventReader::default();
+        self.observe()
+    }
+
+    pub fn step(&mut self, action: &EnvAction) -> EnvStep {
+        if self.is_done() {
+            return EnvStep {
+                observation: self.observe(),
+                reward: 0,
+                done: true,
+            };
+        }
+
+        self.app.insert_resource(PlayerActions {
+            movement: Vec3::new(action.movement[0], 0.0, action.movement[1]),
+            pickup: action.pickup,
+            throw: action.throw.is_some(),
+            aim: action.throw.map(Vec3::from),
+            sprint: action.sprint,
+            dash: action.dash,
+            ..default()
+        });
+        self.app.update();
+
+        let events = self.app.world.resource::<Events<ScoreEvent>>();
+        let reward = self.score_events.iter(events).map(|e| e.score).sum();
+
+        EnvStep {
+            observation: self.observe(),
+            reward,
+            done: self.is_done(),
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        *self.app.world.resource::<State<GameState>>().current() == GameState::GameOver
+    }
+
+    /// Gives access to the simulated world, e.g. to read resources the observation leaves out.
+    pub fn world(&self) -> &World {
+        &self.app.world
+    }
+
+    /// Gives mutable access to the simulated world, e.g. to shorten the round.
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.app.world
+    }
+
+    fn observe(&mut self) -> EnvObservation {
+        let world = &mut self.app.world;
+
+        let (transform, stamina, dash) = world
+            .query_filtered::<(&Transform, &Stamina, &Dash), With<Player>>()
+            .single(world);
+        let player_position = transform.translation.to_array();
+        let (stamina, dash_cooldown) = (stamina.stamina, dash.cooldown);
+
+        let parcel_stack = world.query::<&ParcelStack>().single(world);
+        let stack_capacity = parcel_stack.capacity;
+        let stack_entries = parcel_stack.parcels_entries.clone();
+
+        let stack = stack_entries
+            .iter()
+            .filter_map(|entry| world.get::<ParcelStackEntry>(*entry)?.parcel)
+            .filter_map(|parcel| world.get::<AgentServiceCode>(parcel).copied())
+            .collect();
+
+        let parcels = world
+            .query_filtered::<(
+                &AgentServiceCode,
+                &GlobalTransform,
+                &Velocity,
+                Option<&Picked>,
+            ), (With<Parcel>, Without<Despawn>)>()
+            .iter(world)
+            .map(|(code, transform, velocity, picked)| EnvParcel {
+                code: *code,
+                position: transform.translation().to_array(),
+                velocity: velocity.linvel.to_array(),
+                stacked: picked.is_some(),
+            })
+            .collect();
+
+        EnvObservation {
+            player_position,
+            stack,
+            stack_capacity,
+            parcels,
+            stamina,
+            dash_cooldown,
+            score: world.resource::<Score>().score,
+            time_remaining: world.resource::<TimeRemaining>().timer.remaining_secs(),
+        }
+    }
+}
+
+/// Request sent to [`serve_env`], one JSON object per line.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnvRequest {
+    Reset { seed: u64 },
+    Step(EnvAction),
+}
+
+/// Exposes a [`WarehouseEnv`] over TCP, answering each [`EnvRequest`] line with an
+/// [`EnvObservation`] after a reset or an [`EnvStep`] after a step.
+///
+/// Clients are served one at a time, each one starting from a round seeded with `0`. A client
+/// that fails, e.g. by disconnecting mid-response, is logged and the next one is served.
+pub fn serve_env(addr: impl std::net::ToSocketAddrs) -> std::io::Result<()> {
+    let listener = std::net::TcpListener::bind(addr)?;
+
+    for stream in listener.incoming() {
+        if let Err(err) = stream.and_then(serve_client) {
+            log::warn!("environment client failed: {err}");
+        }
+    }
+
+    Ok(())
+}
+
+fn serve_client(stream: std::net::TcpStream) -> std::io::Result<()> {
+    use std::io::{BufRead, BufReader, Write};
+
+    let mut writer = stream.try_clone()?;
+    let mut env = WarehouseEnv::new(0);
+
+    for line in BufReader::new(stream).lines() {
+        let response = match serde_json::from_str::<EnvRequest>(&line?) {
+            Ok(EnvRequest::Reset { seed }) => serde_json::to_string(&env.reset(seed)),
+            Ok(EnvRequest::Step(action)) => serde_json::to_string(&env.step(&action)),
+            Err(err) => serde_json::to_string(&serde_json::json!({ "error": err.to_string() })),
+        }?;
+
+        writeln!(writer, "{response}")?;
+    }
+
+    Ok(())
+}
@@ -1,28 +1,131 @@
 use bevy::prelude::*;
 
-use crate::{FontAssets, GameState, MainCamera, Score};
+use crate::{
+    storage, DailyChallenge, DailyResult, DailyResults, Difficulty, FontAssets, GameMode,
+    GameState, HighScore, HighScores, MainCamera, RoundStats, Score,
+};
 
 pub struct GameOverPlugin;
 
 impl Plugin for GameOverPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(setup_menu))
-            .add_system_set(SystemSet::on_exit(GameState::GameOver).with_system(cleanup_menu));
+        app.insert_resource(HighScores::load());
+        app.insert_resource(DailyResults::load());
+        app.add_system_set(
+            SystemSet::on_enter(GameState::GameOver)
+                .with_system(record_high_score)
+                .with_system(record_daily_result)
+                .with_system(
+                    setup_menu
+                        .after(record_high_score)
+                        .after(record_daily_result),
+                ),
+        )
+        .add_system_set(SystemSet::on_update(GameState::GameOver).with_system(back_to_menu))
+        .add_system_set(SystemSet::on_exit(GameState::GameOver).with_system(cleanup_menu));
     }
 }
 
 #[derive(Component)]
 pub struct Root;
 
+/// Rank of the round that just ended in the high-score table of its mode.
+#[derive(Resource)]
+struct NewHighScore(Option<usize>);
+
+fn record_high_score(
+    mut commands: Commands,
+    mut high_scores: ResMut<HighScores>,
+    mode: Res<GameMode>,
+    difficulty: Res<Difficulty>,
+    score: Res<Score>,
+    stats: Res<RoundStats>,
+) {
+    let rank = high_scores.record(
+        *mode,
+        HighScore {
+            score: score.score,
+            seconds: stats.elapsed,
+            difficulty: *difficulty,
+        },
+    );
+
+    if rank.is_some() {
+        high_scores.save();
+    }
+
+    commands.insert_resource(NewHighScore(rank));
+}
+
+/// Shareable summary of a daily challenge round, `None` for any other round.
+#[derive(Resource)]
+struct DailyOutcome(Option<DailySummary>);
+
+struct DailySummary {
+    text: String,
+    new_best: bool,
+    exported_to: Option<std::path::PathBuf>,
+}
+
+fn record_daily_result(
+    mut commands: Commands,
+    mut daily_results: ResMut<DailyResults>,
+    daily: Option<Res<DailyChallenge>>,
+    score: Res<Score>,
+    stats: Res<RoundStats>,
+) {
+    let Some(daily) = daily else {
+        commands.insert_resource(DailyOutcome(None));
+        return;
+    };
+
+    let result = DailyResult::new(score.score, &stats);
+    let text = daily.summary(&result);
+    let new_best = daily_results.record(&daily.date, result);
+
+    if new_best {
+        daily_results.save();
+    }
+
+    info!("{text}");
+    let exported_to = storage::export(&daily.summary_file(), &text);
+
+    commands.insert_resource(DailyOutcome(Some(DailySummary {
+        text,
+        new_best,
+        exported_to,
+    })));
+}
+
 fn setup_menu(
     mut commands: Commands,
     font_assets: Res<FontAssets>,
     score: Res<Score>,
+    mode: Res<GameMode>,
+    high_scores: Res<HighScores>,
+    new_high_score: Res<NewHighScore>,
+    daily_outcome: Res<DailyOutcome>,
     query: Query<Entity, With<MainCamera>>,
 ) {
     commands.spawn(Camera2dBundle::default());
     commands.entity(query.single()).despawn();
 
+    let text = |value: String, font_size: f32, color: Color| TextBundle {
+        text: Text {
+            sections: vec![TextSection {
+                value,
+                style: TextStyle {
+                    font: font_assets.montserrat.clone(),
+                    font_size,
+                    color,
+                },
+            }],
+            alignment: Default::default(),
+        },
+        transform: Transform::from_xyz(0.0, 100.0, 0.0),
+        ..Default::default()
+    };
+
     commands
         .spawn(NodeBundle {
             style: Style {
@@ -36,24 +139,91 @@ fn setup_menu(
         })
         .insert(Root)
         .with_children(|parent| {
-            parent.spawn(TextBundle {
-                text: Text {
-                    sections: vec![TextSection {
-                        value: format!(" Score: {:?} ", score.score),
-                        style: TextStyle {
-                            font: font_assets.montserrat.clone(),
-                            font_size: 40.0,
-                            color: Color::rgb(0.9, 0.9, 0.9),
-                        },
-                    }],
-                    alignment: Default::default(),
-                },
-                transform: Transform::from_xyz(0.0, 100.0, 0.0),
-                ..Default::default()
-            });
+            parent.spawn(text(
+                format!(" {} ", mode.name()),
+                30.0,
+                Color::rgb(0.6, 0.6, 0.6),
+            ));
+            parent.spawn(text(
+                format!(" Score: {:?} ", score.score),
+                40.0,
+                Color::rgb(0.9, 0.9, 0.9),
+            ));
+
+            if new_high_score.0.is_some() {
+                parent.spawn(text(
+                    " New high score! ".to_string(),
+                    30.0,
+                    Color::rgb(1.0, 0.85, 0.1),
+                ));
+            }
+
+            if let Some(daily) = &daily_outcome.0 {
+                if daily.new_best {
+                    parent.spawn(text(
+                        " Best run of the day! ".to_string(),
+                        30.0,
+                        Color::rgb(1.0, 0.85, 0.1),
+                    ));
+                }
+
+                for line in daily.text.lines() {
+                    parent.spawn(text(line.to_string(), 24.0, Color::rgb(0.9, 0.9, 0.9)));
+                }
+
+                if let Some(path) = &daily.exported_to {
+                    parent.spawn(text(
+                        format!(" Saved to {} ", path.display()),
+                        16.0,
+                        Color::rgb(0.6, 0.6, 0.6),
+                    ));
+                }
+            } else {
+                high_score_table(parent, &text, &high_scores, *mode, new_high_score.0);
+            }
+
+            parent.spawn(text(
+                " Press Enter to continue ".to_string(),
+                20.0,
+                Color::rgb(0.6, 0.6, 0.6),
+            ));
         });
 }
 
+fn high_score_table(
+    parent: &mut ChildBuilder,
+    text: &impl Fn(String, f32, Color) -> TextBundle,
+    high_scores: &HighScores,
+    mode: GameMode,
+    new_rank: Option<usize>,
+) {
+    for (rank, high_score) in high_scores.table(mode).iter().enumerate() {
+        let color = if new_rank == Some(rank) {
+            Color::rgb(1.0, 0.85, 0.1)
+        } else {
+            Color::rgb(0.9, 0.9, 0.9)
+        };
+
+        parent.spawn(text(
+            format!(
+                "{}. {}  ({:?}, {:.0}s)",
+                rank + 1,
+                high_score.score,
+                high_score.difficulty,
+                high_score.seconds
+            ),
+            24.0,
+            color,
+        ));
+    }
+}
+
+fn back_to_menu(keyboard_input: Res<Input<KeyCode>>, mut app_state: ResMut<State<GameState>>) {
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        app_state.set(GameState::Menu).unwrap();
+    }
+}
+
 fn cleanup_menu(
     mut commands: Commands,
     root: Query<Entity, With<Root>>,
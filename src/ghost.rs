@@ -0,0 +1,189 @@
+//! The player's best run of every mode, replayed by a translucent bird next to the live player.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bob_animator, on_fixed_update, player_sprite, prelude::*, storage, FixedAppExt, GameMode,
+    ImageAssets, Interpolated, Player, RoundEntity, Score,
+};
+
+/// Records the player's path every round and replays the best one of its [`GameMode`], only
+/// added to the windowed game.
+pub struct GhostPlugin;
+
+impl Plugin for GhostPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GhostRuns::load());
+        app.init_resource::<GhostRecording>();
+        app.add_system_set(SystemSet::on_enter(GameState::Ready).with_system(setup_ghost))
+            .add_fixed_system_set(
+                on_fixed_update(GameState::Ready)
+                    .with_system(replay_ghost.before(record_ghost))
+                    .with_system(record_ghost),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(save_ghost_run));
+    }
+}
+
+/// Best run of every [`GameMode`], kept in the data directory.
+#[derive(Resource, Default, Clone, Debug, Serialize, Deserialize)]
+pub struct GhostRuns {
+    pub runs: HashMap<GameMode, GhostRun>,
+}
+
+impl GhostRuns {
+    const FILE: &'static str = "ghost_runs.json";
+
+    pub fn load() -> Self {
+        storage::load(Self::FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::FILE, self);
+    }
+
+    /// Keeps `run` as the best of `mode` if it scored higher, returns whether it did.
+    pub fn record(&mut self, mode: GameMode, run: GhostRun) -> bool {
+        if self
+            .runs
+            .get(&mode)
+            .is_some_and(|best| best.score >= run.score)
+        {
+            return false;
+        }
+
+        self.runs.insert(mode, run);
+        true
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct GhostRun {
+    pub score: i32,
+    /// Where the player was every [`config::GHOST_SAMPLE_STEPS`] fixed steps.
+    pub positions: Vec<Vec3>,
+}
+
+impl GhostRun {
+    /// Where the player was at fixed `step` of the round, `None` once the run is over.
+    pub fn position(&self, step: u32) -> Option<Vec3> {
+        let sample = (step / config::GHOST_SAMPLE_STEPS) as usize;
+        let from = *self.positions.get(sample)?;
+        let to = *self.positions.get(sample + 1).unwrap_or(&from);
+        let t = (step % config::GHOST_SAMPLE_STEPS) as f32 / config::GHOST_SAMPLE_STEPS as f32;
+
+        Some(from.lerp(to, t))
+    }
+}
+
+/// Path of the player in the ongoing round.
+#[derive(Resource, Default)]
+pub struct GhostRecording {
+    /// Fixed steps played in the round.
+    pub step: u32,
+    pub positions: Vec<Vec3>,
+}
+
+#[derive(Component)]
+pub struct Ghost;
+
+#[derive(Component)]
+pub struct GhostGfx;
+
+fn setup_ghost(
+    mut commands: Commands,
+    mut recording: ResMut<GhostRecording>,
+    ghost_runs: Res<GhostRuns>,
+    mode: Res<GameMode>,
+    images: Res<ImageAssets>,
+    mut sprite_params: Sprite3dParams,
+) {
+    *recording = GhostRecording::default();
+
+    let Some(start) = ghost_runs.runs.get(&mode).and_then(|run| run.position(0)) else {
+        return;
+    };
+
+    let mut sprite = player_sprite(&images).bundle(&mut sprite_params);
+    // the sprite's material is shared with the player's, the ghost gets its own see-through one
+    let mut material = sprite_params
+        .materials
+        .get(&sprite.pbr.material)
+        .unwrap()
+        .clone();
+    material.base_color = Color::rgba(1.0, 1.0, 1.0, config::GHOST_ALPHA);
+    material.alpha_mode = AlphaMode::Blend;
+    sprite.pbr.material = sprite_params.materials.add(material);
+
+    commands
+        .spawn((
+            SpatialBundle::from_transform(Transform::from_translation(start)),
+            Ghost,
+            RoundEntity,
+            Interpolated::default(),
+            Name::new("Ghost"),
+        ))
+        .with_children(|b| {
+            b.spawn((sprite, GhostGfx, bob_animator(), Name::new("Ghost Gfx")));
+        });
+}
+
+fn record_ghost(mut recording: ResMut<GhostRecording>, player: Query<&Transform, With<Player>>) {
+    if recording.step.is_multiple_of(config::GHOST_SAMPLE_STEPS) {
+        recording.positions.push(player.single().translation);
+    }
+    recording.step += 1;
+}
+
+fn replay_ghost(
+    recording: Res<GhostRecording>,
+    ghost_runs: Res<GhostRuns>,
+    mode: Res<GameMode>,
+    mut ghost: Query<(&mut Transform, &mut Visibility), (With<Ghost>, Without<GhostGfx>)>,
+    mut ghost_gfx: Query<&mut Transform, With<GhostGfx>>,
+) {
+    let Ok((mut transform, mut visibility)) = ghost.get_single_mut() else {
+        return;
+    };
+
+    let Some(position) = ghost_runs
+        .runs
+        .get(&mode)
+        .and_then(|run| run.position(recording.step))
+    else {
+        visibility.is_visible = false;
+        return;
+    };
+
+    // faces where it's going, like the player's sprite
+    let mut gfx_transform = ghost_gfx.single_mut();
+    if position.x > transform.translation.x {
+        gfx_transform.rotation = Quat::from_rotation_y(180.0_f32.to_radians());
+    } else if position.x < transform.translation.x {
+        gfx_transform.rotation = Quat::from_rotation_y(0.0_f32.to_radians());
+    }
+
+    transform.translation = position;
+}
+
+fn save_ghost_run(
+    mut ghost_runs: ResMut<GhostRuns>,
+    mut recording: ResMut<GhostRecording>,
+    mode: Res<GameMode>,
+    score: Res<Score>,
+) {
+    if recording.positions.is_empty() {
+        return;
+    }
+
+    let run = GhostRun {
+        score: score.score,
+        positions: std::mem::take(&mut recording.positions),
+    };
+
+    if ghost_runs.record(*mode, run) {
+        ghost_runs.save();
+    }
+}
@@ -0,0 +1,85 @@
+use std::time::Duration;
+
+use bevy::{asset::AssetPlugin, core::CorePlugin, scene::ScenePlugin};
+
+use crate::{add_gameplay, prelude::*, AudioAssets, FontAssets, ImageAssets, ModelAssets, Score};
+
+/// Simulated seconds advanced every [`App::update`] of a headless app, exactly one fixed step.
+pub const HEADLESS_TIMESTEP: f32 = config::FIXED_TIMESTEP;
+
+/// Time the clock of a headless app advances every [`App::update`], [`HEADLESS_TIMESTEP`]
+/// unless replaced to play at another frame rate.
+#[derive(Resource)]
+pub struct HeadlessFrameTime(pub Duration);
+
+impl Default for HeadlessFrameTime {
+    fn default() -> Self {
+        Self(Duration::from_secs_f32(HEADLESS_TIMESTEP))
+    }
+}
+
+/// Builds the game without window, renderer, audio or asset loading so rounds can be
+/// simulated as fast as possible, e.g. to benchmark bots.
+///
+/// The clock advances by [`HeadlessFrameTime`] each update and the app starts directly in
+/// [`GameState::Ready`] with placeholder assets. Nothing drives the player, add a
+/// [`BotPlugin`] or write [`PlayerActions`] between updates.
+pub fn setup_headless_app(app: &mut App) -> &mut App {
+    app.add_plugin(CorePlugin::default())
+        .add_plugin(TransformPlugin)
+        .add_plugin(HierarchyPlugin)
+        .add_plugin(AssetPlugin::default())
+        .add_plugin(ScenePlugin)
+        .add_asset::<Mesh>()
+        .add_asset::<Image>()
+        .add_asset::<StandardMaterial>()
+        .add_asset::<TextureAtlas>()
+        .add_asset::<ColorMaterial>()
+        .init_resource::<Time>()
+        .init_resource::<HeadlessFrameTime>()
+        .init_resource::<DebugLines>()
+        .add_system_to_stage(CoreStage::First, step_headless_time);
+
+    insert_placeholder_assets(&mut app.world);
+
+    add_gameplay(app);
+
+    app.add_state(GameState::Ready);
+
+    app
+}
+
+/// Updates a headless app until the round is over and returns the final score.
+pub fn run_headless_round(app: &mut App) -> i32 {
+    while *app.world.resource::<State<GameState>>().current() != GameState::GameOver {
+        app.update();
+    }
+
+    app.world.resource::<Score>().score
+}
+
+fn step_headless_time(mut time: ResMut<Time>, frame_time: Res<HeadlessFrameTime>) {
+    let now = time.last_update().unwrap_or_else(|| time.startup()) + frame_time.0;
+    time.update_with_instant(now);
+}
+
+fn insert_placeholder_assets(world: &mut World) {
+    let image = world.resource_mut::<Assets<Image>>().add(Image::default());
+
+    world.insert_resource(ImageAssets {
+        bird: image.clone(),
+        dhl: image.clone(),
+        postnord: image.clone(),
+        bring: image.clone(),
+        budbee: image,
+    });
+    world.insert_resource(ModelAssets {
+        truck: Handle::default(),
+    });
+    world.insert_resource(FontAssets {
+        montserrat: Handle::default(),
+    });
+    world.insert_resource(AudioAssets {
+        anthem: Handle::default(),
+    });
+}
@@ -1,23 +1,71 @@
+mod achievements;
+mod bot;
+mod cli;
+mod daily;
 mod debug;
+mod difficulty;
+mod env;
 mod game_over;
+mod ghost;
+mod headless;
+mod menu;
+mod modes;
+mod mutators;
 mod parcels;
 mod player;
+mod powerups;
 pub mod prelude;
+mod replay;
+mod settings;
+mod shake;
+mod snapshot;
 mod state;
+pub mod storage;
+#[cfg(all(feature = "telemetry", not(target_arch = "wasm32")))]
+mod telemetry;
+mod throw;
+mod timestep;
+mod tournament;
 mod ui;
 mod warehouse;
+mod waves;
 
+use bevy::audio::AudioSink;
 use bevy::core_pipeline::clear_color::ClearColorConfig;
 use bevy::core_pipeline::tonemapping::Tonemapping;
-use bevy::window::PresentMode;
 use bevy_asset_loader::prelude::*;
 use bevy_embedded_assets::EmbeddedAssetPlugin;
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
 
+pub use crate::achievements::*;
+pub use crate::bot::*;
+pub use crate::cli::*;
+pub use crate::daily::*;
+pub use crate::difficulty::*;
+pub use crate::env::*;
 pub use crate::game_over::*;
+pub use crate::ghost::*;
+pub use crate::headless::*;
+pub use crate::menu::*;
+pub use crate::modes::*;
+pub use crate::mutators::*;
 pub use crate::parcels::*;
 pub use crate::player::*;
+pub use crate::powerups::*;
+pub use crate::replay::*;
+pub use crate::settings::*;
+pub use crate::shake::*;
+pub use crate::snapshot::*;
+#[cfg(all(feature = "telemetry", not(target_arch = "wasm32")))]
+pub use crate::telemetry::*;
+pub use crate::throw::*;
+pub use crate::timestep::*;
+pub use crate::tournament::*;
 pub use crate::ui::*;
 pub use crate::warehouse::*;
+pub use crate::waves::*;
 pub use prelude::*;
 
 #[derive(AssetCollection, Resource)]
@@ -53,21 +101,23 @@ pub struct FontAssets {
 }
 
 pub fn setup_app(app: &mut App) -> &mut App {
+    let settings = Settings::load();
+
     app.add_plugins(
         DefaultPlugins
             .set(ImagePlugin::default_nearest())
             .set(WindowPlugin {
                 window: WindowDescriptor {
                     title: "Black Friday".to_string(),
-                    present_mode: PresentMode::AutoNoVsync,
-                    width: 1280.0,
-                    height: 720.0,
+                    present_mode: settings.present_mode(),
+                    width: settings.resolution.0,
+                    height: settings.resolution.1,
                     position: WindowPosition::Automatic,
                     scale_factor_override: Some(1.0),
                     resizable: true,
                     decorations: true,
                     cursor_visible: true,
-                    mode: WindowMode::Windowed,
+                    mode: settings.window_mode(),
                     transparent: false,
                     fit_canvas_to_parent: true,
                     ..default()
@@ -79,28 +129,37 @@ pub fn setup_app(app: &mut App) -> &mut App {
             .add_before::<AssetPlugin, EmbeddedAssetPlugin>(EmbeddedAssetPlugin),
     )
     //.add_plugin(WorldInspectorPlugin::new())
-    .add_plugin(RapierPhysicsPlugin::<NoUserData>::default())
     //.add_plugin(RapierDebugRenderPlugin::default())
     .add_plugin(DebugLinesPlugin::with_depth_test(true))
-    .add_plugin(Sprite3dPlugin)
-    .add_plugin(TweeningPlugin)
-    .add_plugin(PlayerPlugin)
-    .add_plugin(ParcelsPlugin)
-    .add_plugin(WarehousePlugin)
     .add_plugin(OutlinePlugin)
+    .add_plugin(PlayerInputPlugin)
+    .add_plugin(ThrowAimPlugin)
+    .add_plugin(DifficultySelectPlugin)
+    .add_plugin(MutatorSelectPlugin)
+    .add_plugin(MenuPlugin)
+    .add_plugin(GameOverPlugin)
+    .add_plugin(GhostPlugin)
+    .add_plugin(AchievementsPlugin)
+    .add_plugin(RenderInterpolationPlugin)
+    .add_plugin(SnapshotPlugin)
+    .add_plugin(SettingsPlugin)
+    .add_plugin(CameraShakePlugin)
     .add_plugin(UiPlugin);
 
+    add_gameplay(app);
+
     app.add_state(GameState::Loading)
         .add_loading_state(
             LoadingState::new(GameState::Loading)
-                .continue_to_state(GameState::Ready)
+                .continue_to_state(GameState::Menu)
                 .with_collection::<ImageAssets>()
                 .with_collection::<FontAssets>()
                 .with_collection::<AudioAssets>()
                 .with_collection::<ModelAssets>(),
         )
+        .add_system_set(SystemSet::on_exit(GameState::Loading).with_system(play_anthem))
         .add_system_set(SystemSet::on_enter(GameState::Ready).with_system(setup))
-        .add_system_set(SystemSet::on_update(GameState::Ready).with_system(check_game_over));
+        .add_system_set(SystemSet::on_exit(GameState::GameOver).with_system(despawn_round));
 
     app.add_system_set(SystemSet::on_enter(GameState::Loading).with_system(setup_loading))
         .add_system_set(SystemSet::on_exit(GameState::Loading).with_system(clean_loading));
@@ -108,18 +167,69 @@ pub fn setup_app(app: &mut App) -> &mut App {
     app
 }
 
+/// Adds the simulation of a round, shared by the windowed game and [`setup_headless_app`].
+pub fn add_gameplay(app: &mut App) -> &mut App {
+    app.add_plugin(FixedTimestepPlugin)
+        .add_plugin(Sprite3dPlugin)
+        // instead of the `TweeningPlugin`, tweens scale colliders so they advance with the steps
+        .add_fixed_event::<TweenCompleted>()
+        .add_fixed_system_set(SystemSet::new().with_system(component_animator_system::<Transform>))
+        .add_plugin(PlayerPlugin)
+        .add_plugin(ParcelsPlugin)
+        .add_plugin(WarehousePlugin)
+        .add_plugin(PowerUpsPlugin)
+        .add_plugin(DifficultyPlugin)
+        .add_plugin(WavesPlugin)
+        .add_plugin(ModesPlugin)
+        .add_plugin(MutatorsPlugin)
+        .init_resource::<GameRng>()
+        .add_system_set(SystemSet::on_enter(GameState::Ready).with_system(setup_round));
+
+    #[cfg(all(feature = "telemetry", not(target_arch = "wasm32")))]
+    app.add_plugin(TelemetryPlugin);
+
+    app
+}
+
 #[derive(Resource)]
 pub struct TimeRemaining {
     pub timer: Timer,
 }
 
+/// Source of all gameplay randomness, insert a seeded one before the round starts to replay
+/// the same parcel sequence.
+///
+/// ChaCha12 like rand's `StdRng`, but with a state that snapshots can keep.
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct GameRng(pub ChaCha12Rng);
+
+impl GameRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self(ChaCha12Rng::seed_from_u64(seed))
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self(ChaCha12Rng::from_entropy())
+    }
+}
+
+/// Seeds the [`GameRng`] of every round started from the menu, a new seed every round if `None`.
+#[derive(Resource, Default)]
+pub struct FixedSeed(pub Option<u64>);
+
 #[derive(Component)]
 struct FaceCamera;
 
 #[derive(Component)]
 pub struct MainCamera;
 
-fn setup(mut commands: Commands, audio_assets: Res<AudioAssets>, audio: Res<Audio>) {
+/// Despawned along with its children once the round is over and the game returns to the menu.
+#[derive(Component)]
+pub struct RoundEntity;
+
+fn setup(mut commands: Commands) {
     // camera
     commands.spawn((
         Camera3dBundle {
@@ -136,23 +246,38 @@ fn setup(mut commands: Commands, audio_assets: Res<AudioAssets>, audio: Res<Audi
             ..Default::default()
         },
         MainCamera,
+        CameraShake::default(),
     ));
+}
 
-    audio.play(audio_assets.anthem.clone());
+/// The anthem, its volume follows the [`Settings`].
+#[derive(Resource)]
+pub struct Music(pub Handle<AudioSink>);
+
+fn play_anthem(
+    mut commands: Commands,
+    audio_assets: Res<AudioAssets>,
+    audio: Res<Audio>,
+    sinks: Res<Assets<AudioSink>>,
+    settings: Res<Settings>,
+) {
+    let sink = audio.play_with_settings(
+        audio_assets.anthem.clone(),
+        PlaybackSettings::ONCE.with_volume(settings.music()),
+    );
+    commands.insert_resource(Music(sinks.get_handle(sink)));
+}
 
+fn setup_round(mut commands: Commands) {
     commands.insert_resource(TimeRemaining {
         timer: Timer::from_seconds(config::GAME_TIME, TimerMode::Once),
     });
+    commands.insert_resource(Score::default());
 }
 
-fn check_game_over(
-    mut app_state: ResMut<State<GameState>>,
-    time: Res<Time>,
-    mut time_remaining: ResMut<TimeRemaining>,
-) {
-    time_remaining.timer.tick(time.delta());
-    if time_remaining.timer.just_finished() {
-        app_state.set(GameState::GameOver).unwrap();
+fn despawn_round(mut commands: Commands, round: Query<Entity, With<RoundEntity>>) {
+    for entity in &round {
+        commands.entity(entity).despawn_recursive();
     }
 }
 
@@ -163,7 +288,6 @@ fn setup_loading(mut commands: Commands, asset_server: Res<AssetServer>) {
     commands.spawn(Camera2dBundle {
         camera_2d: Camera2d {
             clear_color: ClearColorConfig::Custom(Color::rgb(0.0, 0.0, 0.0)),
-            ..default()
         },
         ..default()
     });
@@ -184,7 +308,7 @@ fn setup_loading(mut commands: Commands, asset_server: Res<AssetServer>) {
             parent.spawn(TextBundle {
                 text: Text {
                     sections: vec![TextSection {
-                        value: format!("Loading ..."),
+                        value: "Loading ...".to_string(),
                         style: TextStyle {
                             font: asset_server.load("fonts/Montserrat-Regular.ttf").clone(),
                             font_size: 40.0,
@@ -1,16 +1,50 @@
 use std::io::Cursor;
 
 use bevy::{window::WindowId, winit::WinitWindows};
-use nordicnest_bf::prelude::*;
+use nordicnest_bf::{
+    prelude::*, run_headless_round, setup_headless_app, Cli, CliError, WindowOptions,
+};
 use winit::window::Icon;
 
 fn main() {
+    let cli = match Cli::parse(std::env::args().skip(1)) {
+        Ok(cli) => cli,
+        Err(CliError::Help) => {
+            print!("{}", CliError::Help);
+            return;
+        }
+        Err(err) => {
+            eprintln!("error: {err}\n\nRun with --help to see the options.");
+            std::process::exit(2);
+        }
+    };
+
+    if cli.headless {
+        simulate(&cli);
+        return;
+    }
+
     let mut app = App::new();
     nordicnest_bf::setup_app(&mut app);
+    cli.configure(&mut app, 0);
+    app.insert_resource(cli.window);
     app.add_startup_system(set_window_icon);
+    app.add_startup_system(apply_window_options);
     app.run();
 }
 
+/// Plays the rounds without a window and prints their scores.
+fn simulate(cli: &Cli) {
+    for round in 0..cli.rounds {
+        let mut app = App::new();
+        setup_headless_app(&mut app);
+        cli.configure(&mut app, round);
+
+        let score = run_headless_round(&mut app);
+        println!("round {}: score {score}", round + 1);
+    }
+}
+
 fn set_window_icon(windows: NonSend<WinitWindows>) {
     let primary = windows.get_window(WindowId::primary()).unwrap();
     let icon_buf = Cursor::new(include_bytes!("../assets/images/nordicnest_bird.png"));
@@ -22,3 +56,7 @@ fn set_window_icon(windows: NonSend<WinitWindows>) {
         primary.set_window_icon(Some(icon));
     };
 }
+
+fn apply_window_options(options: Res<WindowOptions>, mut windows: ResMut<Windows>) {
+    options.apply(windows.primary_mut());
+}
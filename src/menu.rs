@@ -0,0 +1,255 @@
+use crate::{
+    prelude::*, Achievement, AchievementProgress, DailyChallenge, Difficulty, FixedSeed,
+    FontAssets, GameMode, GameRng, Mutator, Mutators,
+};
+
+/// Picks the game mode before a round, only added to the windowed game.
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<FixedSeed>();
+        app.add_system_set(SystemSet::on_enter(GameState::Menu).with_system(setup_menu))
+            .add_system_set(
+                SystemSet::on_update(GameState::Menu)
+                    .with_system(select_mode)
+                    .with_system(update_difficulty_text)
+                    .with_system(update_mutators_text),
+            )
+            .add_system_set(SystemSet::on_exit(GameState::Menu).with_system(cleanup_menu))
+            .add_system_set(SystemSet::on_update(GameState::Ready).with_system(leave_round))
+            .add_system_set(SystemSet::on_exit(GameState::GameOver).with_system(restore_selection));
+    }
+}
+
+#[derive(Component)]
+pub struct MenuRoot;
+
+#[derive(Component)]
+pub struct MenuDifficultyText;
+
+#[derive(Component)]
+pub struct MenuMutatorsText;
+
+/// Difficulty and mutators picked before the daily challenge took them over for its round.
+#[derive(Resource)]
+pub struct SelectionBeforeDaily {
+    pub difficulty: Difficulty,
+    pub mutators: Mutators,
+}
+
+const MODE_KEYS: [KeyCode; 4] = [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4];
+
+fn setup_menu(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    achievements: Res<AchievementProgress>,
+) {
+    commands.spawn(Camera2dBundle::default());
+
+    let style = |font_size: f32, color: Color| TextStyle {
+        font: font_assets.montserrat.clone(),
+        font_size,
+        color,
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                margin: UiRect::all(Val::Auto),
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(MenuRoot)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Black Friday",
+                style(60.0, Color::rgb(0.9, 0.9, 0.9)),
+            ));
+
+            for (i, mode) in GameMode::ALL.iter().enumerate() {
+                let description = match mode {
+                    GameMode::Timed => format!("{} seconds on the clock", config::GAME_TIME),
+                    GameMode::Endless => format!(
+                        "until {} parcels are lost or mis-sorted",
+                        config::ENDLESS_MAX_MISSES
+                    ),
+                    GameMode::Zen => "no clock, no penalties".to_string(),
+                    GameMode::SuddenDeath => "the first wrong delivery ends it".to_string(),
+                };
+
+                parent.spawn(TextBundle::from_section(
+                    format!("{} - {}: {}", i + 1, mode.name(), description),
+                    style(30.0, Color::rgb(0.9, 0.9, 0.9)),
+                ));
+            }
+
+            let daily = DailyChallenge::today();
+            parent.spawn(TextBundle::from_section(
+                format!(
+                    "D - Daily challenge {}: {} on {:?}, mutators: {}",
+                    daily.date,
+                    daily.mode.name(),
+                    daily.difficulty,
+                    daily.mutators.describe()
+                ),
+                style(30.0, Color::rgb(1.0, 0.85, 0.1)),
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                "S - Settings",
+                style(24.0, Color::rgb(0.9, 0.9, 0.9)),
+            ));
+
+            parent.spawn((
+                TextBundle::from_section("", style(24.0, Color::rgb(0.6, 0.6, 0.6))),
+                MenuDifficultyText,
+            ));
+
+            parent.spawn((
+                TextBundle::from_section("", style(20.0, Color::rgb(0.6, 0.6, 0.6))),
+                MenuMutatorsText,
+            ));
+
+            let unlocked = Achievement::ALL
+                .iter()
+                .filter(|achievement| achievements.is_unlocked(**achievement))
+                .count();
+            parent.spawn(TextBundle::from_section(
+                format!("Achievements {}/{}:", unlocked, Achievement::ALL.len()),
+                style(20.0, Color::rgb(0.6, 0.6, 0.6)),
+            ));
+
+            for achievement in Achievement::ALL {
+                let color = if achievements.is_unlocked(achievement) {
+                    Color::rgb(1.0, 0.85, 0.1)
+                } else {
+                    Color::rgb(0.6, 0.6, 0.6)
+                };
+
+                parent.spawn(TextBundle::from_section(
+                    format!(
+                        "{} - {} ({}/{})",
+                        achievement.name(),
+                        achievement.description(),
+                        achievements.get(achievement),
+                        achievement.goal()
+                    ),
+                    style(18.0, color),
+                ));
+            }
+        });
+}
+
+fn select_mode(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut mode: ResMut<GameMode>,
+    mut difficulty: ResMut<Difficulty>,
+    mut mutators: ResMut<Mutators>,
+    fixed_seed: Res<FixedSeed>,
+    mut app_state: ResMut<State<GameState>>,
+) {
+    if let Some(i) = MODE_KEYS
+        .iter()
+        .position(|key| keyboard_input.just_pressed(*key))
+    {
+        *mode = GameMode::ALL[i];
+        commands.insert_resource(
+            fixed_seed
+                .0
+                .map_or_else(GameRng::default, GameRng::from_seed),
+        );
+        commands.remove_resource::<DailyChallenge>();
+        app_state.set(GameState::Ready).unwrap();
+    } else if keyboard_input.just_pressed(KeyCode::D) {
+        let daily = DailyChallenge::today();
+        commands.insert_resource(SelectionBeforeDaily {
+            difficulty: *difficulty,
+            mutators: mutators.clone(),
+        });
+        *mode = daily.mode;
+        *difficulty = daily.difficulty;
+        *mutators = daily.mutators.clone();
+        commands.insert_resource(daily.rng());
+        commands.insert_resource(daily);
+        app_state.set(GameState::Ready).unwrap();
+    } else if keyboard_input.just_pressed(KeyCode::S) {
+        app_state.set(GameState::Settings).unwrap();
+    }
+}
+
+fn update_difficulty_text(
+    difficulty: Res<Difficulty>,
+    mut text: Query<&mut Text, With<MenuDifficultyText>>,
+) {
+    for mut text in &mut text {
+        text.sections[0].value = format!("Difficulty: {difficulty:?} (F1, F2, F3 to change)");
+    }
+}
+
+fn update_mutators_text(
+    mutators: Res<Mutators>,
+    mut text: Query<&mut Text, With<MenuMutatorsText>>,
+) {
+    let lines = Mutator::ALL
+        .iter()
+        .enumerate()
+        .map(|(i, mutator)| {
+            format!(
+                "F{} [{}] {} x{}",
+                i + 5,
+                if mutators.is_active(*mutator) {
+                    "x"
+                } else {
+                    " "
+                },
+                mutator.name(),
+                mutator.score_multiplier()
+            )
+        })
+        .collect::<Vec<_>>();
+
+    for mut text in &mut text {
+        text.sections[0].value = format!(
+            "Mutators, score x{:.2}:\n{}",
+            mutators.score_multiplier(),
+            lines.join("\n")
+        );
+    }
+}
+
+fn cleanup_menu(
+    mut commands: Commands,
+    root: Query<Entity, With<MenuRoot>>,
+    cam: Query<Entity, With<Camera2d>>,
+) {
+    commands.entity(root.single()).despawn_recursive();
+    commands.entity(cam.single()).despawn_recursive();
+}
+
+/// Gives the player back their own selection once the daily challenge is over.
+fn restore_selection(
+    mut commands: Commands,
+    selection: Option<Res<SelectionBeforeDaily>>,
+    mut difficulty: ResMut<Difficulty>,
+    mut mutators: ResMut<Mutators>,
+) {
+    let Some(selection) = selection else {
+        return;
+    };
+
+    *difficulty = selection.difficulty;
+    *mutators = selection.mutators.clone();
+    commands.remove_resource::<SelectionBeforeDaily>();
+}
+
+/// Ends the round early with Escape, the only way out of a Zen round.
+fn leave_round(keyboard_input: Res<Input<KeyCode>>, mut app_state: ResMut<State<GameState>>) {
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        let _ = app_state.set(GameState::GameOver);
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    collect_parcels, despawn_out_of_bounds, on_fixed_update, prelude::*, storage, Difficulty,
+    FixedAppExt, ParcelDelivered, ParcelLost, TimeRemaining,
+};
+
+/// Ends the round according to the selected [`GameMode`].
+pub struct ModesPlugin;
+
+impl Plugin for ModesPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameMode>();
+        app.init_resource::<RoundStats>();
+        app.add_system_set(SystemSet::on_enter(GameState::Ready).with_system(reset_round_stats))
+            .add_fixed_system_set(
+                on_fixed_update(GameState::Ready)
+                    .with_system(
+                        track_round_stats
+                            .after(collect_parcels)
+                            .after(despawn_out_of_bounds),
+                    )
+                    .with_system(check_round_end.after(track_round_stats)),
+            );
+    }
+}
+
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum GameMode {
+    /// Scores as much as possible within [`config::GAME_TIME`].
+    #[default]
+    Timed,
+    /// Goes on until [`config::ENDLESS_MAX_MISSES`] parcels were lost or mis-sorted.
+    Endless,
+    /// Goes on until the player leaves, wrong deliveries cost nothing.
+    Zen,
+    /// Ends with the first wrong delivery.
+    SuddenDeath,
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 4] = [
+        GameMode::Timed,
+        GameMode::Endless,
+        GameMode::Zen,
+        GameMode::SuddenDeath,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameMode::Timed => "Timed",
+            GameMode::Endless => "Endless",
+            GameMode::Zen => "Zen",
+            GameMode::SuddenDeath => "Sudden Death",
+        }
+    }
+
+    pub fn has_timer(&self) -> bool {
+        *self == GameMode::Timed
+    }
+
+    pub fn has_penalties(&self) -> bool {
+        *self != GameMode::Zen
+    }
+
+    pub fn is_over(&self, stats: &RoundStats, time_remaining: &TimeRemaining) -> bool {
+        match self {
+            GameMode::Timed => time_remaining.timer.finished(),
+            GameMode::Endless => stats.misses() >= config::ENDLESS_MAX_MISSES,
+            GameMode::Zen => false,
+            GameMode::SuddenDeath => stats.wrong > 0,
+        }
+    }
+}
+
+/// What happened to the parcels so far this round.
+#[derive(Resource, Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RoundStats {
+    pub correct: u32,
+    pub wrong: u32,
+    pub lost: u32,
+    /// Seconds played.
+    pub elapsed: f32,
+}
+
+impl RoundStats {
+    /// Parcels lost or mis-sorted.
+    pub fn misses(&self) -> u32 {
+        self.wrong + self.lost
+    }
+}
+
+fn reset_round_stats(mut stats: ResMut<RoundStats>) {
+    *stats = RoundStats::default();
+}
+
+pub fn track_round_stats(
+    time: Res<Time>,
+    mut stats: ResMut<RoundStats>,
+    mut delivered: EventReader<ParcelDelivered>,
+    mut lost: EventReader<ParcelLost>,
+) {
+    stats.elapsed += time.delta_seconds();
+
+    for event in delivered.iter() {
+        if event.is_correct() {
+            stats.correct += 1;
+        } else {
+            stats.wrong += 1;
+        }
+    }
+
+    stats.lost += lost.iter().count() as u32;
+}
+
+fn check_round_end(
+    mode: Res<GameMode>,
+    stats: Res<RoundStats>,
+    time: Res<Time>,
+    mut app_state: ResMut<State<GameState>>,
+    mut time_remaining: ResMut<TimeRemaining>,
+) {
+    if mode.has_timer() {
+        time_remaining.timer.tick(time.delta());
+    }
+
+    if mode.is_over(&stats, &time_remaining) {
+        // the player may have left the round this frame already
+        let _ = app_state.set(GameState::GameOver);
+    }
+}
+
+/// Best rounds of every [`GameMode`], kept in the data directory.
+#[derive(Resource, Default, Clone, Debug, Serialize, Deserialize)]
+pub struct HighScores {
+    pub tables: HashMap<GameMode, Vec<HighScore>>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HighScore {
+    pub score: i32,
+    /// Seconds the round lasted.
+    pub seconds: f32,
+    pub difficulty: Difficulty,
+}
+
+impl HighScores {
+    const FILE: &'static str = "high_scores.json";
+
+    pub fn load() -> Self {
+        storage::load(Self::FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::FILE, self);
+    }
+
+    /// Best rounds of `mode`, best first.
+    pub fn table(&self, mode: GameMode) -> &[HighScore] {
+        self.tables.get(&mode).map_or(&[], Vec::as_slice)
+    }
+
+    /// Adds a round to the table of `mode` and returns its rank, `None` if it didn't make it.
+    pub fn record(&mut self, mode: GameMode, entry: HighScore) -> Option<usize> {
+        let table = self.tables.entry(mode).or_default();
+        let rank = table
+            .iter()
+            .position(|high_score| entry.score > high_score.score)
+            .unwrap_or(table.len());
+
+        if rank >= config::HIGH_SCORE_ENTRIES {
+            return None;
+        }
+
+        table.insert(rank, entry);
+        table.truncate(config::HIGH_SCORE_ENTRIES);
+        Some(rank)
+    }
+}
@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+/// Applies the selected [`Mutators`] to the world at the start of a round.
+pub struct MutatorsPlugin;
+
+impl Plugin for MutatorsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Mutators>();
+        app.add_system_set(SystemSet::on_enter(GameState::Ready).with_system(apply_gravity));
+    }
+}
+
+/// Toggles mutators in the menu with F5 to F10, only added to the windowed game.
+pub struct MutatorSelectPlugin;
+
+impl Plugin for MutatorSelectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(SystemSet::on_update(GameState::Menu).with_system(select_mutators));
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Mutator {
+    /// Scales the Rapier gravity by [`config::LOW_GRAVITY_FACTOR`].
+    LowGravity,
+    /// Parcels slide around with [`config::SLIPPERY_PARCEL_FRICTION`].
+    SlipperyFloor,
+    /// Swaps left and right.
+    MirroredControls,
+    /// Parcels no longer show the color and logo of their carrier.
+    InvisibleLabels,
+    /// Regular spawning runs twice as often, waves are unchanged.
+    DoubleSpawnRate,
+    /// Parcels are [`config::GIANT_PARCEL_SCALE`] times bigger.
+    GiantParcels,
+}
+
+impl Mutator {
+    pub const ALL: [Mutator; 6] = [
+        Mutator::LowGravity,
+        Mutator::SlipperyFloor,
+        Mutator::MirroredControls,
+        Mutator::InvisibleLabels,
+        Mutator::DoubleSpawnRate,
+        Mutator::GiantParcels,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mutator::LowGravity => "Low Gravity",
+            Mutator::SlipperyFloor => "Slippery Floor",
+            Mutator::MirroredControls => "Mirrored Controls",
+            Mutator::InvisibleLabels => "Invisible Labels",
+            Mutator::DoubleSpawnRate => "Double Spawn Rate",
+            Mutator::GiantParcels => "Giant Parcels",
+        }
+    }
+
+    /// Factor applied to the points of correctly sorted parcels.
+    pub fn score_multiplier(&self) -> f32 {
+        match self {
+            Mutator::LowGravity => 1.1,
+            Mutator::SlipperyFloor => 1.2,
+            Mutator::MirroredControls => 1.5,
+            Mutator::InvisibleLabels => 1.5,
+            Mutator::DoubleSpawnRate => 1.25,
+            // bigger parcels are easier to push into the right area
+            Mutator::GiantParcels => 0.9,
+        }
+    }
+}
+
+/// Mutators of the next or current round, in the order of [`Mutator::ALL`].
+#[derive(Resource, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Mutators(pub Vec<Mutator>);
+
+impl Mutators {
+    pub fn is_active(&self, mutator: Mutator) -> bool {
+        self.0.contains(&mutator)
+    }
+
+    pub fn toggle(&mut self, mutator: Mutator) {
+        if self.is_active(mutator) {
+            self.0.retain(|active| *active != mutator);
+        } else {
+            self.0.push(mutator);
+            self.0
+                .sort_by_key(|active| Mutator::ALL.iter().position(|m| m == active));
+        }
+    }
+
+    /// Product of the multipliers of every active mutator.
+    pub fn score_multiplier(&self) -> f32 {
+        self.0.iter().map(Mutator::score_multiplier).product()
+    }
+
+    /// Names of the active mutators, "None" without any.
+    pub fn describe(&self) -> String {
+        if self.0.is_empty() {
+            return "None".to_string();
+        }
+
+        self.0
+            .iter()
+            .map(Mutator::name)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+pub fn apply_gravity(mutators: Res<Mutators>, mut rapier_config: ResMut<RapierConfiguration>) {
+    let gravity = RapierConfiguration::default().gravity;
+
+    rapier_config.gravity = if mutators.is_active(Mutator::LowGravity) {
+        gravity * config::LOW_GRAVITY_FACTOR
+    } else {
+        gravity
+    };
+}
+
+fn select_mutators(keyboard_input: Res<Input<KeyCode>>, mut mutators: ResMut<Mutators>) {
+    const MUTATOR_KEYS: [KeyCode; 6] = [
+        KeyCode::F5,
+        KeyCode::F6,
+        KeyCode::F7,
+        KeyCode::F8,
+        KeyCode::F9,
+        KeyCode::F10,
+    ];
+
+    for (key, mutator) in MUTATOR_KEYS.iter().zip(Mutator::ALL) {
+        if keyboard_input.just_pressed(*key) {
+            mutators.toggle(mutator);
+        }
+    }
+}
@@ -1,26 +1,82 @@
-use std::time::Duration;
+use std::{collections::HashMap, time::Duration};
 
-use bevy_spatial::{RTreeAccess3D, RTreePlugin3D};
+use bevy::ecs::system::SystemParam;
+use bevy_spatial::{RTreeAccess3D, RTreePlugin3D, SpatialAccess};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
 
-use crate::{prelude::*, AgentServiceCode, ImageAssets, PopParcelFromStack};
+use crate::{
+    on_fixed_update, parcel_stack_events, play_spawn_timeline, pop_despawning_parcels_from_pick,
+    prelude::*, setup_ground, ActiveWave, AgentServiceCode, DifficultyCurve, FixedAppExt,
+    FixedStage, GameRng, ImageAssets, Interpolated, Mutator, Mutators, Picked, PopParcelFromStack,
+    RoundEntity, Thrown, TimeScale,
+};
 pub struct ParcelsPlugin;
 
 impl Plugin for ParcelsPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugin(RTreePlugin3D::<Parcel> { ..default() });
-        app.add_system_set(SystemSet::on_enter(GameState::Ready).with_system(setup_parcel_spawner))
-            .add_system_set(
-                SystemSet::on_update(GameState::Ready)
-                    .with_system(spawn_parcels)
-                    .with_system(despawn_out_of_bounds)
-                    .with_system(despawn_with_timer),
-            );
+        app.insert_resource(ParcelsSpatialTree::from(RTreePlugin3D::<Parcel>::default()));
+        app.add_fixed_event::<ParcelLost>();
+        // bodies are spawned in the same order every round, Rapier only repeats a simulation
+        // that inserts them in the same order
+        app.add_system_set(
+            SystemSet::on_enter(GameState::Ready)
+                .with_system(setup_parcel_spawner.after(setup_ground)),
+        )
+        .add_fixed_system_set(
+            on_fixed_update(GameState::Ready)
+                .with_system(recycle_parcels.before(spawn_parcels))
+                .with_system(spawn_parcels)
+                .with_system(despawn_out_of_bounds.after(play_spawn_timeline))
+                .with_system(
+                    despawn_with_timer
+                        .after(play_spawn_timeline)
+                        .after(pop_despawning_parcels_from_pick)
+                        .before(parcel_stack_events),
+                ),
+        )
+        .add_fixed_system_set_to_stage(
+            FixedStage::PostUpdate,
+            SystemSet::new().with_system(update_spatial_tree),
+        );
     }
 }
 
 #[derive(Component)]
 pub struct Parcel;
 
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ParcelKind {
+    Standard,
+    Small,
+    /// Bigger and much heavier, barely budges when pushed.
+    Heavy,
+}
+
+impl ParcelKind {
+    pub const ALL: [ParcelKind; 3] = [ParcelKind::Standard, ParcelKind::Small, ParcelKind::Heavy];
+
+    pub fn size(&self) -> f32 {
+        match self {
+            ParcelKind::Standard => config::PARCEL_SIZE,
+            ParcelKind::Small => config::PARCEL_SIZE * 0.6,
+            ParcelKind::Heavy => config::PARCEL_SIZE * 1.25,
+        }
+    }
+
+    pub fn density(&self) -> f32 {
+        match self {
+            ParcelKind::Heavy => 4.0,
+            _ => 1.0,
+        }
+    }
+}
+
+/// Sent when a parcel fell off the warehouse without being delivered.
+pub struct ParcelLost {
+    pub code: AgentServiceCode,
+}
+
 #[derive(Component)]
 pub struct Despawn {
     pub timer: Timer,
@@ -28,6 +84,19 @@ pub struct Despawn {
 
 pub type ParcelsSpatialTree = RTreeAccess3D<Parcel>; // type alias for brevity
 
+/// Rebuilt after every fixed step, so every step queries where the parcels are in that step.
+pub fn update_spatial_tree(
+    mut tree: ResMut<ParcelsSpatialTree>,
+    parcels: Query<(Entity, &Transform), With<Parcel>>,
+) {
+    tree.recreate(
+        parcels
+            .iter()
+            .map(|(entity, transform)| (transform.translation, entity))
+            .collect(),
+    );
+}
+
 impl Despawn {
     pub fn from_secs(sec: u64) -> Self {
         Self {
@@ -46,9 +115,38 @@ pub struct ParcelSpawner {
     pub timer: Timer,
     pub parent: Entity,
     pub count: u64,
+    /// Seconds of the round played, slowed down along with the spawning.
+    pub elapsed: f32,
+    /// Regular spawning is paused until this many seconds into the round.
+    pub calm_until: f32,
+    /// Index of the next wave of the [`SpawnTimeline`](crate::SpawnTimeline) to start.
+    pub next_wave: usize,
+    /// Seconds into the round the current pass over the timeline started at.
+    pub timeline_start: f32,
+    /// Waves still dropping parcels one at a time.
+    pub active_waves: Vec<ActiveWave>,
 }
 
-fn setup_parcel_spawner(mut commands: Commands) {
+/// Parcels deactivated instead of despawned, reused by [`ParcelCommands::spawn`].
+#[derive(Resource, Default)]
+pub struct ParcelPool {
+    pub free: HashMap<ParcelKind, Vec<Entity>>,
+}
+
+/// Marks a hidden parcel without collisions waiting in the [`ParcelPool`].
+#[derive(Component)]
+pub struct Pooled;
+
+/// Out of sight and out of the way of the warehouse.
+const POOL_POSITION: Vec3 = Vec3::new(0.0, -100.0, 0.0);
+
+pub fn setup_parcel_spawner(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    texture_assets: Res<ImageAssets>,
+    mutators: Res<Mutators>,
+) {
     let parcel_parent = commands
         .spawn((
             Name::new("Parcels Container"),
@@ -58,6 +156,16 @@ fn setup_parcel_spawner(mut commands: Commands) {
             },
         ))
         .id();
+    commands.entity(parcel_parent).insert(RoundEntity);
+
+    fill_parcel_pool(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &texture_assets,
+        &mutators,
+        parcel_parent,
+    );
     commands.insert_resource(ParcelSpawner {
         timer: Timer::new(
             Duration::from_millis(config::PARCEL_SPAWN_RATE),
@@ -65,116 +173,308 @@ fn setup_parcel_spawner(mut commands: Commands) {
         ),
         parent: parcel_parent,
         count: 0,
+        elapsed: 0.0,
+        calm_until: 0.0,
+        next_wave: 0,
+        timeline_start: 0.0,
+        active_waves: vec![],
     })
 }
 
-fn spawn_parcels(
+/// Swaps every parcel of the round, pooled or not, for bodies built with the current
+/// [`Mutators`], for rounds that change their mutators midway or are restored from a snapshot.
+pub fn rebuild_parcels(
     mut commands: Commands,
-    time: Res<Time>,
-    mut spawner: ResMut<ParcelSpawner>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     texture_assets: Res<ImageAssets>,
+    mutators: Res<Mutators>,
+    spawner: Res<ParcelSpawner>,
+    parcels: Query<Entity, With<ParcelKind>>,
 ) {
-    spawner.timer.tick(time.delta());
+    for parcel in &parcels {
+        commands.entity(parcel).despawn_recursive();
+    }
 
-    if !spawner.timer.just_finished() {
+    fill_parcel_pool(
+        &mut commands,
+        &mut meshes,
+        &mut materials,
+        &texture_assets,
+        &mutators,
+        spawner.parent,
+    );
+}
+
+/// Builds the [`ParcelAssets`] of the mutators and a new [`ParcelPool`] of their bodies.
+fn fill_parcel_pool(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    texture_assets: &ImageAssets,
+    mutators: &Mutators,
+    parent: Entity,
+) {
+    let assets = ParcelAssets::new(meshes, materials, texture_assets, mutators);
+
+    // picked up by `recycle_parcels` once the pool is in place
+    for kind in ParcelKind::ALL {
+        for _ in 0..config::PARCEL_POOL_SIZE {
+            let parcel = commands.spawn(parcel_body(&assets, mutators, kind)).id();
+            deactivate_parcel(commands, parcel, parent);
+        }
+    }
+
+    commands.insert_resource(assets);
+    commands.insert_resource(ParcelPool::default());
+}
+
+fn recycle_parcels(
+    mut pool: ResMut<ParcelPool>,
+    pooled: Query<(Entity, &ParcelKind), Added<Pooled>>,
+) {
+    for (entity, kind) in &pooled {
+        pool.free.entry(*kind).or_default().push(entity);
+    }
+}
+
+/// Hides `parcel` and takes it out of the simulation until it's reused from the [`ParcelPool`].
+pub fn deactivate_parcel(commands: &mut Commands, parcel: Entity, parent: Entity) {
+    commands
+        .entity(parcel)
+        .remove::<(Parcel, Picked, Thrown, Despawn, Sensor, Animator<Transform>)>()
+        .insert((
+            Pooled,
+            Visibility { is_visible: false },
+            RigidBody::Fixed,
+            CollisionGroups::new(Group::NONE, Group::NONE),
+            Velocity::zero(),
+            Transform::from_translation(POOL_POSITION),
+        ))
+        .set_parent(parent);
+}
+
+pub fn spawn_parcels(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    curve: Res<DifficultyCurve>,
+    mutators: Res<Mutators>,
+    mut spawner: ResMut<ParcelSpawner>,
+    mut rng: ResMut<GameRng>,
+    mut parcels: ParcelCommands,
+) {
+    let delta = time.delta().mul_f32(time_scale.0);
+    spawner.elapsed += delta.as_secs_f32();
+
+    if spawner.elapsed < spawner.calm_until {
+        // keeps the timer paused without it reporting the last spawn again
+        spawner.timer.tick(Duration::ZERO);
         return;
     }
 
-    // random agent code
-    let agent_code = match rand::random::<u8>() % 4 {
-        0 => AgentServiceCode::PostNord,
-        1 => AgentServiceCode::DHL,
-        2 => AgentServiceCode::Bring,
-        3 => AgentServiceCode::Budbee,
-        _ => unreachable!(),
+    let (_, tier) = curve.tier_at(spawner.elapsed);
+    let interval = if mutators.is_active(Mutator::DoubleSpawnRate) {
+        Duration::from_secs_f32(tier.spawn_interval / 2.0)
+    } else {
+        Duration::from_secs_f32(tier.spawn_interval)
     };
+    if spawner.timer.duration() != interval {
+        spawner.timer.set_duration(interval);
+    }
 
-    let mut cube_mesh = Mesh::from(shape::Cube {
-        size: config::PARCEL_SIZE,
-    });
-    cube_mesh.generate_outline_normals().unwrap();
+    spawner.timer.tick(delta);
 
-    commands
-        .spawn((
-            PbrBundle {
-                mesh: meshes.add(cube_mesh),
-                material: materials.add(StandardMaterial {
-                    base_color: agent_code.color(),
-                    base_color_texture: match agent_code {
-                        AgentServiceCode::PostNord => Some(texture_assets.postnord.clone()),
-                        AgentServiceCode::DHL => Some(texture_assets.dhl.clone()),
-                        AgentServiceCode::Bring => Some(texture_assets.bring.clone()),
-                        AgentServiceCode::Budbee => Some(texture_assets.budbee.clone()),
-                    },
-                    alpha_mode: AlphaMode::Blend,
-                    depth_bias: 5.0,
-                    ..default()
-                }),
-                transform: Transform::from_translation(rand_parcel_spawn()),
-                ..Default::default()
-            },
-            agent_code,
-            RigidBody::Dynamic,
-            Velocity {
-                linvel: rand_parcel_linvel(),
-                angvel: Vec3::new(1.0, 0.0, 0.0),
-            },
-            Collider::cuboid(
-                config::PARCEL_SIZE / 2.,
-                config::PARCEL_SIZE / 2.,
-                config::PARCEL_SIZE / 2.,
-            ),
-            Parcel,
-            Friction {
-                coefficient: 2.0,
-                combine_rule: CoefficientCombineRule::Average,
-            },
-            OutlineBundle {
-                outline: OutlineVolume {
-                    visible: false,
-                    colour: Color::rgba(1.0, 1.0, 1.0, 0.8),
-                    width: 4.0,
+    if !spawner.timer.just_finished() {
+        return;
+    }
+
+    for _ in 0..tier.burst_size(&mut rng.0) {
+        let code = tier.carrier(&mut rng.0);
+        let kind = tier.kind(&mut rng.0);
+        let position = rand_parcel_spawn(&mut rng.0);
+        let linvel = rand_parcel_linvel(&mut rng.0);
+
+        parcels.spawn(&mut spawner, position, linvel, code, kind);
+    }
+}
+
+/// Meshes and materials shared by every parcel of a round, one per kind and one per carrier.
+#[derive(Resource)]
+pub struct ParcelAssets {
+    pub meshes: HashMap<ParcelKind, Handle<Mesh>>,
+    pub materials: HashMap<AgentServiceCode, Handle<StandardMaterial>>,
+}
+
+/// Edge length of parcels of `kind` with the mutators of the round.
+pub fn parcel_size(kind: ParcelKind, mutators: &Mutators) -> f32 {
+    if mutators.is_active(Mutator::GiantParcels) {
+        kind.size() * config::GIANT_PARCEL_SCALE
+    } else {
+        kind.size()
+    }
+}
+
+impl ParcelAssets {
+    /// Built every round, the mutators decide on the size and the labels of parcels.
+    fn new(
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<StandardMaterial>,
+        texture_assets: &ImageAssets,
+        mutators: &Mutators,
+    ) -> Self {
+        let labeled = !mutators.is_active(Mutator::InvisibleLabels);
+
+        let meshes = ParcelKind::ALL
+            .into_iter()
+            .map(|kind| {
+                let mut cube_mesh = Mesh::from(shape::Cube {
+                    size: parcel_size(kind, mutators),
+                });
+                cube_mesh.generate_outline_normals().unwrap();
+
+                (kind, meshes.add(cube_mesh))
+            })
+            .collect();
+
+        let materials = [
+            (AgentServiceCode::PostNord, &texture_assets.postnord),
+            (AgentServiceCode::DHL, &texture_assets.dhl),
+            (AgentServiceCode::Bring, &texture_assets.bring),
+            (AgentServiceCode::Budbee, &texture_assets.budbee),
+        ]
+        .into_iter()
+        .map(|(code, texture)| {
+            let material = materials.add(StandardMaterial {
+                base_color: if labeled {
+                    code.color()
+                } else {
+                    Color::rgb(0.6, 0.45, 0.3)
                 },
+                base_color_texture: labeled.then(|| texture.clone()),
+                alpha_mode: AlphaMode::Blend,
+                depth_bias: 5.0,
                 ..default()
+            });
+
+            (code, material)
+        })
+        .collect();
+
+        Self { meshes, materials }
+    }
+}
+
+/// Components of a parcel of `kind` that stay the same when it's reused.
+fn parcel_body(assets: &ParcelAssets, mutators: &Mutators, kind: ParcelKind) -> impl Bundle {
+    let size = parcel_size(kind, mutators);
+    let friction = if mutators.is_active(Mutator::SlipperyFloor) {
+        config::SLIPPERY_PARCEL_FRICTION
+    } else {
+        2.0
+    };
+
+    (
+        PbrBundle {
+            mesh: assets.meshes[&kind].clone(),
+            ..Default::default()
+        },
+        kind,
+        Collider::cuboid(size / 2., size / 2., size / 2.),
+        ColliderMassProperties::Density(kind.density()),
+        Friction {
+            coefficient: friction,
+            combine_rule: CoefficientCombineRule::Average,
+        },
+        OutlineBundle {
+            outline: OutlineVolume {
+                visible: false,
+                colour: Color::rgba(1.0, 1.0, 1.0, 0.8),
+                width: 4.0,
             },
-            Name::new("Parcel".to_string()),
-        ))
-        .set_parent(spawner.parent);
+            ..default()
+        },
+        Interpolated::default(),
+        Name::new("Parcel".to_string()),
+    )
+}
 
-    spawner.count += 1;
+/// Spawns parcels into the [`ParcelSpawner`] container, reusing pooled ones when possible.
+#[derive(SystemParam)]
+pub struct ParcelCommands<'w, 's> {
+    commands: Commands<'w, 's>,
+    assets: Res<'w, ParcelAssets>,
+    mutators: Res<'w, Mutators>,
+    pool: ResMut<'w, ParcelPool>,
+}
 
-    if spawner.count % config::PARCEL_LEVEL_UP == 0 {
-        let new_rate = (config::PARCEL_LEVEL_UP_DECR * (spawner.count / config::PARCEL_LEVEL_UP))
-            .min(config::PARCEL_LEVEL_UP_MIN);
+impl<'w, 's> ParcelCommands<'w, 's> {
+    /// Queues other commands in order with the spawns.
+    pub fn commands(&mut self) -> &mut Commands<'w, 's> {
+        &mut self.commands
+    }
 
-        spawner.timer = Timer::new(
-            Duration::from_millis(config::PARCEL_SPAWN_RATE - new_rate),
-            TimerMode::Repeating,
-        );
+    pub fn spawn(
+        &mut self,
+        spawner: &mut ParcelSpawner,
+        position: Vec3,
+        linvel: Vec3,
+        code: AgentServiceCode,
+        kind: ParcelKind,
+    ) -> Entity {
+        spawner.count += 1;
+
+        let parcel = match self.pool.free.get_mut(&kind).and_then(Vec::pop) {
+            Some(parcel) => parcel,
+            None => self
+                .commands
+                .spawn(parcel_body(&self.assets, &self.mutators, kind))
+                .set_parent(spawner.parent)
+                .id(),
+        };
+
+        self.commands.entity(parcel).remove::<Pooled>().insert((
+            Transform::from_translation(position),
+            Visibility { is_visible: true },
+            self.assets.materials[&code].clone(),
+            code,
+            Parcel,
+            RigidBody::Dynamic,
+            Velocity {
+                linvel,
+                angvel: Vec3::new(1.0, 0.0, 0.0),
+            },
+            GravityScale(1.0),
+            CollisionGroups::default(),
+        ));
+
+        parcel
     }
 }
 
-fn despawn_out_of_bounds(
+pub fn despawn_out_of_bounds(
     mut commands: Commands,
-    mut query: Query<(Entity, &Transform), (With<Parcel>, Without<Despawn>)>,
+    mut events: EventWriter<ParcelLost>,
+    spawner: Res<ParcelSpawner>,
+    mut query: Query<(Entity, &Transform, &AgentServiceCode), (With<Parcel>, Without<Despawn>)>,
 ) {
     const DESPAWN_HEIGHT: f32 = -30.0;
     const DISABLE_COLLISION_HEIGHT: f32 = -5.0;
 
-    for (entity, transform) in query.iter_mut() {
-        if transform.translation.y <= DISABLE_COLLISION_HEIGHT {
-            commands.entity(entity).remove::<Collider>();
-        }
+    for (entity, transform, code) in query.iter_mut() {
         if transform.translation.y <= DESPAWN_HEIGHT {
-            commands.entity(entity).despawn();
+            deactivate_parcel(&mut commands, entity, spawner.parent);
+            events.send(ParcelLost { code: *code });
+        } else if transform.translation.y <= DISABLE_COLLISION_HEIGHT {
+            commands
+                .entity(entity)
+                .insert(CollisionGroups::new(Group::NONE, Group::NONE));
         }
     }
 }
 
 fn despawn_with_timer(
     mut commands: Commands,
+    spawner: Res<ParcelSpawner>,
     time: Res<Time>,
     mut query: Query<(Entity, &mut OutlineVolume, &mut Despawn)>,
     mut events: EventWriter<PopParcelFromStack>,
@@ -183,18 +483,21 @@ fn despawn_with_timer(
         despawn.timer.tick(time.delta());
         volume.visible = false;
         if despawn.timer.just_finished() {
-            commands.entity(entity).despawn();
+            deactivate_parcel(&mut commands, entity, spawner.parent);
             events.send(PopParcelFromStack {
                 parcel_entry: entity,
                 despawning: true,
+                aim: None,
+                power: 1.0,
             });
         }
     }
 }
 
-fn rand_parcel_spawn() -> Vec3 {
+pub fn rand_parcel_spawn(rng: &mut impl Rng) -> Vec3 {
     const PADDING: f32 = 1.25;
     let point = random_point_in_area(
+        rng,
         Vec3::new(
             -config::GROUND_SIZE / 2.0 + PADDING,
             0.0,
@@ -210,10 +513,10 @@ fn rand_parcel_spawn() -> Vec3 {
     Vec3::new(point.x, config::PARCEL_SPAWN_Y, point.z)
 }
 
-pub fn rand_parcel_linvel() -> Vec3 {
-    let x = rand::random::<f32>() * config::PARCEL_MAX_LINVEL_X;
+pub fn rand_parcel_linvel(rng: &mut impl Rng) -> Vec3 {
+    let x = rng.gen::<f32>() * config::PARCEL_MAX_LINVEL_X;
     let y = 0.0;
-    let z = rand::random::<f32>() * config::PARCEL_MAX_LINVEL_Z;
+    let z = rng.gen::<f32>() * config::PARCEL_MAX_LINVEL_Z;
 
     Vec3::new(
         x.max(config::PARCEL_MIN_LINVEL_X),
@@ -222,10 +525,10 @@ pub fn rand_parcel_linvel() -> Vec3 {
     )
 }
 
-fn random_point_in_area(a: Vec3, b: Vec3) -> Vec3 {
-    let x = rand::random::<f32>() * (b.x - a.x) + a.x;
-    let y = rand::random::<f32>() * (b.y - a.y) + a.y;
-    let z = rand::random::<f32>() * (b.z - a.z) + a.z;
+fn random_point_in_area(rng: &mut impl Rng, a: Vec3, b: Vec3) -> Vec3 {
+    let x = rng.gen::<f32>() * (b.x - a.x) + a.x;
+    let y = rng.gen::<f32>() * (b.y - a.y) + a.y;
+    let z = rng.gen::<f32>() * (b.z - a.z) + a.z;
 
     Vec3::new(x, y, z)
 }
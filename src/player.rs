@@ -2,9 +2,13 @@ use std::time::Duration;
 
 use bevy_spatial::SpatialAccess;
 use bevy_tweening::lens::TransformScaleLens;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    prelude::*, Despawn, FaceCamera, ImageAssets, Parcel, ParcelSpawner, ParcelsSpatialTree,
+    apply_magnet, apply_scanner, on_fixed_update, play_spawn_timeline, prelude::*,
+    setup_parcel_spawner, throw_velocity, update_score, Despawn, FaceCamera, FixedAppExt,
+    FixedStage, ImageAssets, Interpolated, Mutator, Mutators, Parcel, ParcelSpawner,
+    ParcelsSpatialTree, RoundEntity, Score,
 };
 
 pub struct PlayerPlugin;
@@ -12,44 +16,231 @@ pub struct PlayerPlugin;
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(ClosestParcel(None));
+        app.init_resource::<PlayerActions>();
+        app.add_fixed_event::<AddParcelToStack>();
+        app.add_fixed_event::<PopParcelFromStack>();
+        app.add_fixed_event::<ParcelThrown>();
+        app.add_system_set(
+            SystemSet::on_enter(GameState::Ready).with_system(setup.after(setup_parcel_spawner)),
+        )
+        // stack events are sent and handled in the same step, in the same order every step
+        .add_fixed_system_set(
+            on_fixed_update(GameState::Ready)
+                .with_system(player_movement)
+                .with_system(
+                    dash_shove_parcels
+                        .after(player_movement)
+                        .after(apply_magnet),
+                )
+                .with_system(respawn_fallen_player.after(player_movement))
+                .with_system(parcel_awarness.after(player_movement))
+                .with_system(pickup_parcel.after(apply_scanner))
+                .with_system(balance_parcel_stack.after(respawn_fallen_player))
+                .with_system(pop_parcel.after(pickup_parcel).after(balance_parcel_stack))
+                .with_system(pop_despawning_parcels_from_pick.after(pop_parcel))
+                .with_system(
+                    parcel_stack_events
+                        .after(pop_despawning_parcels_from_pick)
+                        .after(dash_shove_parcels)
+                        .after(play_spawn_timeline),
+                )
+                .with_system(maintain_parcel_stack.after(parcel_stack_events))
+                .with_system(remove_outline_from_picked.after(parcel_stack_events))
+                .with_system(
+                    upgrade_stack_capacity
+                        .after(maintain_parcel_stack)
+                        .after(update_score),
+                ),
+        )
+        .add_fixed_system_set_to_stage(
+            FixedStage::PostUpdate,
+            SystemSet::new().with_system(clear_one_off_actions),
+        );
+    }
+}
+
+/// Drives the player from keyboard & mouse, only added to the windowed game.
+pub struct PlayerInputPlugin;
+
+impl Plugin for PlayerInputPlugin {
+    fn build(&self, app: &mut App) {
         app.insert_resource(MousePosition::default());
-        app.add_event::<AddParcelToStack>();
-        app.add_event::<PopParcelFromStack>();
-        app.add_system_set(SystemSet::on_enter(GameState::Ready).with_system(setup))
-            .add_system_set(
-                SystemSet::on_update(GameState::Ready)
-                    .with_system(update_mouse_hover_pos)
-                    .with_system(player_movement)
-                    .with_system(parcel_awarness)
-                    .with_system(parcel_stack_events)
-                    .with_system(maintain_parcel_stack)
-                    .with_system(pickup_parcel)
-                    .with_system(pop_parcel)
-                    .with_system(pop_despawning_parcels_from_pick)
-                    .with_system(remove_outline_from_picked),
-            );
+        app.init_resource::<ThrowCharge>();
+        app.init_resource::<StackSelection>();
+        app.add_system_set(
+            SystemSet::on_update(GameState::Ready)
+                .with_system(update_mouse_hover_pos)
+                .with_system(
+                    player_input
+                        .label(WritePlayerActions)
+                        .after(update_mouse_hover_pos),
+                ),
+        );
+    }
+}
+
+/// What the player wants to do this frame, written either by [`PlayerInputPlugin`] or a bot.
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerActions {
+    /// Movement direction on the ground plane, normalized before use.
+    pub movement: Vec3,
+    pub pickup: bool,
+    pub throw: bool,
+    /// Point on the ground the top parcel is thrown towards, dropped in place if `None`.
+    pub aim: Option<Vec3>,
+    /// Multiplier of the throw velocity, `1.0` throws exactly towards `aim`.
+    pub power: f32,
+    /// Stack slot to throw counting from the bottom, the top parcel is thrown if `None` or the
+    /// slot is empty.
+    pub slot: Option<usize>,
+    /// Throws every stacked parcel at once, fanned out around `aim`.
+    pub throw_all: bool,
+    pub drop_bottom: bool,
+    /// Moves faster while there is stamina left.
+    pub sprint: bool,
+    /// Dashes in the movement direction if the dash has cooled down.
+    pub dash: bool,
+}
+
+impl Default for PlayerActions {
+    fn default() -> Self {
+        Self {
+            movement: Vec3::ZERO,
+            pickup: false,
+            throw: false,
+            aim: None,
+            power: 1.0,
+            slot: None,
+            throw_all: false,
+            drop_bottom: false,
+            sprint: false,
+            dash: false,
+        }
+    }
+}
+
+impl PlayerActions {
+    /// Takes over `actions`, keeping the one-off actions of earlier frames no fixed step acted
+    /// on yet.
+    pub fn replace(&mut self, actions: PlayerActions) {
+        *self = PlayerActions {
+            pickup: self.pickup || actions.pickup,
+            throw: self.throw || actions.throw,
+            throw_all: self.throw_all || actions.throw_all,
+            drop_bottom: self.drop_bottom || actions.drop_bottom,
+            dash: self.dash || actions.dash,
+            ..actions
+        };
+    }
+}
+
+/// Stack slot picked with the number keys for the next throw.
+#[derive(Resource, Default, Debug, Clone)]
+pub struct StackSelection(pub Option<usize>);
+
+/// How long the throw input has been held.
+#[derive(Resource, Default, Debug, Clone)]
+pub struct ThrowCharge {
+    pub seconds: f32,
+    /// Held for too long, the parcel was dropped and nothing is thrown on release.
+    pub fumbled: bool,
+}
+
+impl ThrowCharge {
+    /// Charge between `0.0` and `1.0`.
+    pub fn fraction(&self) -> f32 {
+        (self.seconds / config::PLAYER_THROW_CHARGE_TIME).min(1.0)
+    }
+
+    pub fn power(&self) -> f32 {
+        config::PLAYER_MIN_THROW_POWER
+            + (config::PLAYER_MAX_THROW_POWER - config::PLAYER_MIN_THROW_POWER) * self.fraction()
     }
 }
 
+/// Label for systems writing [`PlayerActions`], the systems consuming them run after it.
+#[derive(SystemLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct WritePlayerActions;
+
 #[derive(Component)]
 pub struct Player;
 
 #[derive(Component)]
 pub struct PlayerGfx;
 
+/// Movement state of the player's character controller.
+#[derive(Component, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlayerBody {
+    /// Velocity on the ground plane, eased towards the wanted velocity.
+    pub velocity: Vec3,
+    pub vertical_speed: f32,
+}
+
+/// Drained by sprinting and recovered while not sprinting.
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Stamina {
+    pub stamina: f32,
+}
+
+impl Stamina {
+    /// Stamina between `0.0` and `1.0`.
+    pub fn fraction(&self) -> f32 {
+        self.stamina / config::PLAYER_MAX_STAMINA
+    }
+}
+
+#[derive(Component, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Dash {
+    /// Seconds until the next dash is possible.
+    pub cooldown: f32,
+    /// Seconds left of the ongoing dash.
+    pub remaining: f32,
+    direction: Vec3,
+}
+
+impl Dash {
+    pub fn is_dashing(&self) -> bool {
+        self.remaining > 0.0
+    }
+
+    /// How far the dash has cooled down, `1.0` when ready.
+    pub fn readiness(&self) -> f32 {
+        1.0 - self.cooldown / config::PLAYER_DASH_COOLDOWN
+    }
+}
+
 #[derive(Component)]
-struct ParcelStack {
-    parcels_entries: Vec<Entity>,
+pub struct ParcelStack {
+    pub parcels_entries: Vec<Entity>,
+    /// How many parcels can be stacked, upgraded during the round by scoring.
+    pub capacity: usize,
+}
+
+/// Sway of the stack, modelled as a damped spring kicked by changes of the player velocity.
+#[derive(Component, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct StackBalance {
+    /// Lean of the stack on the ground plane as `(x, z)`, its length is the angle in radians.
+    pub tilt: Vec2,
+    pub tilt_velocity: Vec2,
+    last_position: Option<Vec3>,
+    last_velocity: Vec3,
 }
 
 #[derive(Component)]
-struct ParcelStackEntry {
-    parcel: Option<Entity>,
+pub struct ParcelStackEntry {
+    pub parcel: Option<Entity>,
 }
 
 #[derive(Component)]
 pub struct Picked;
 
+/// Marks a parcel thrown from the stack until it's picked up again.
+#[derive(Component)]
+pub struct Thrown {
+    /// Where the parcel left the stack.
+    pub from: Vec3,
+}
+
 fn setup(mut commands: Commands, images: Res<ImageAssets>, mut sprite_params: Sprite3dParams) {
     // player
     commands
@@ -64,10 +255,28 @@ fn setup(mut commands: Commands, images: Res<ImageAssets>, mut sprite_params: Sp
             KinematicCharacterController {
                 slide: true,
                 apply_impulse_to_dynamic_bodies: true,
+                autostep: Some(CharacterAutostep {
+                    max_height: CharacterLength::Absolute(config::PLAYER_STEP_HEIGHT),
+                    min_width: CharacterLength::Absolute(0.2),
+                    // walk into parcels to push them instead of climbing them
+                    include_dynamic_bodies: false,
+                }),
+                max_slope_climb_angle: config::PLAYER_MAX_SLOPE.to_radians(),
+                min_slope_slide_angle: config::PLAYER_MAX_SLOPE.to_radians(),
+                snap_to_ground: Some(CharacterLength::Absolute(0.2)),
+                // shipping areas and stacked parcels
+                filter_flags: QueryFilterFlags::EXCLUDE_SENSORS,
                 ..default()
             },
+            PlayerBody::default(),
             FaceCamera,
             Player,
+            RoundEntity,
+            Stamina {
+                stamina: config::PLAYER_MAX_STAMINA,
+            },
+            Dash::default(),
+            Interpolated::default(),
             Name::new("Player"),
         ))
         .add_children(|b| {
@@ -79,37 +288,48 @@ fn setup(mut commands: Commands, images: Res<ImageAssets>, mut sprite_params: Sp
                 Name::new("Parcel Stack"),
                 ParcelStack {
                     parcels_entries: vec![],
+                    capacity: config::PLAYER_STACK_CAPACITY,
                 },
+                StackBalance::default(),
             ));
             b.spawn((
-                Sprite3d {
-                    image: images.bird.clone(),
-                    pixels_per_metre: 600.,
-                    partial_alpha: true,
-                    unlit: true,
-                    double_sided: true,
-                    pivot: Some(Vec2::new(0.5, 0.3)),
-                    ..default()
-                }
-                .bundle(&mut sprite_params),
+                player_sprite(&images).bundle(&mut sprite_params),
                 Name::new("Player Gfx"),
                 PlayerGfx,
-                Animator::new(
-                    Tween::new(
-                        EaseFunction::QuadraticInOut,
-                        Duration::from_millis(666),
-                        TransformScaleLens {
-                            start: Vec3::new(1., 1., 1.),
-                            end: Vec3::new(0.85, 0.85, 0.85),
-                        },
-                    )
-                    .with_repeat_count(RepeatCount::Infinite)
-                    .with_repeat_strategy(RepeatStrategy::MirroredRepeat),
-                ),
+                bob_animator(),
             ));
         });
 }
 
+/// The bird drawn for the player.
+pub fn player_sprite(images: &ImageAssets) -> Sprite3d {
+    Sprite3d {
+        image: images.bird.clone(),
+        pixels_per_metre: 600.,
+        partial_alpha: true,
+        unlit: true,
+        double_sided: true,
+        pivot: Some(Vec2::new(0.5, 0.3)),
+        ..default()
+    }
+}
+
+/// Squashes the bird's sprite in and out while it waddles around.
+pub fn bob_animator() -> Animator<Transform> {
+    Animator::new(
+        Tween::new(
+            EaseFunction::QuadraticInOut,
+            Duration::from_millis(666),
+            TransformScaleLens {
+                start: Vec3::new(1., 1., 1.),
+                end: Vec3::new(0.85, 0.85, 0.85),
+            },
+        )
+        .with_repeat_count(RepeatCount::Infinite)
+        .with_repeat_strategy(RepeatStrategy::MirroredRepeat),
+    )
+}
+
 pub struct AddParcelToStack {
     pub parcel: Entity,
 }
@@ -117,15 +337,26 @@ pub struct AddParcelToStack {
 pub struct PopParcelFromStack {
     pub parcel_entry: Entity,
     pub despawning: bool,
+    /// Point on the ground the parcel is thrown towards, dropped in place if `None`.
+    pub aim: Option<Vec3>,
+    pub power: f32,
 }
 
-fn parcel_stack_events(
+/// Sent when a parcel leaves the stack towards an aimed point.
+pub struct ParcelThrown {
+    pub parcel: Entity,
+    pub from: Vec3,
+    pub aim: Vec3,
+    pub velocity: Vec3,
+}
+
+pub fn parcel_stack_events(
     mut commands: Commands,
     mut events: EventReader<AddParcelToStack>,
     mut pop_events: EventReader<PopParcelFromStack>,
+    mut thrown_events: EventWriter<ParcelThrown>,
     mut parcel_stack: Query<(&mut ParcelStack, Entity)>,
     spawner: Res<ParcelSpawner>,
-    mouse_pos: Res<MousePosition>,
     mut parcel_stack_entries: Query<&mut ParcelStackEntry>,
     mut parcels: Query<
         (
@@ -141,27 +372,7 @@ fn parcel_stack_events(
         let (mut stack, stack_entity) = parcel_stack.single_mut();
 
         let index = stack.parcels_entries.len();
-
-        let entry = commands
-            .spawn((
-                TransformBundle {
-                    local: Transform::from_translation(get_parcel_stack_pos(index)),
-                    ..Default::default()
-                },
-                VisibilityBundle::default(),
-                ParcelStackEntry {
-                    parcel: Some(event.parcel),
-                },
-            ))
-            .set_parent(stack_entity)
-            .id();
-
-        commands
-            .entity(event.parcel)
-            .insert(Picked)
-            .set_parent(entry)
-            .insert(GravityScale(0.0))
-            .insert(Sensor);
+        let entry = stack_parcel(&mut commands, stack_entity, index, event.parcel);
 
         if let Ok((mut transform, mut velocity, mut outline, _)) = parcels.get_mut(event.parcel) {
             transform.translation = Vec3::ZERO;
@@ -197,13 +408,18 @@ fn parcel_stack_events(
                     if let Ok((mut transform, mut velocity, _, global)) = parcels.get_mut(parcel) {
                         transform.translation = global.translation();
 
-                        if let Some(pos) = mouse_pos.0 {
-                            let linvel = ((pos - transform.translation)
-                                * config::PLAYER_THROW_FACTOR)
-                                .clamp_length_max(config::PLAYER_MAX_THROW_MAQ)
-                                .clamp_length_min(2.0);
-
-                            velocity.linvel = linvel + Vec3::Y * 7.;
+                        if let Some(pos) = event.aim {
+                            velocity.linvel =
+                                throw_velocity(transform.translation, pos, event.power);
+
+                            let from = transform.translation;
+                            commands.entity(parcel).insert(Thrown { from });
+                            thrown_events.send(ParcelThrown {
+                                parcel,
+                                from,
+                                aim: pos,
+                                velocity: velocity.linvel,
+                            });
                         } else {
                             velocity.linvel = Vec3::NEG_Y * 0.05;
                         }
@@ -216,7 +432,39 @@ fn parcel_stack_events(
     }
 }
 
-fn pop_despawning_parcels_from_pick(
+/// Puts `parcel` into a new entry at `index` of the stack and returns the entry.
+pub fn stack_parcel(
+    commands: &mut Commands,
+    stack: Entity,
+    index: usize,
+    parcel: Entity,
+) -> Entity {
+    let entry = commands
+        .spawn((
+            TransformBundle {
+                local: Transform::from_translation(get_parcel_stack_pos(index)),
+                ..Default::default()
+            },
+            VisibilityBundle::default(),
+            ParcelStackEntry {
+                parcel: Some(parcel),
+            },
+        ))
+        .set_parent(stack)
+        .id();
+
+    commands
+        .entity(parcel)
+        .insert(Picked)
+        .remove::<Thrown>()
+        .set_parent(entry)
+        .insert(GravityScale(0.0))
+        .insert(Sensor);
+
+    entry
+}
+
+pub fn pop_despawning_parcels_from_pick(
     mut commands: Commands,
     mut events: EventWriter<PopParcelFromStack>,
     parcels: Query<(Entity, &Picked, &Despawn)>,
@@ -226,11 +474,13 @@ fn pop_despawning_parcels_from_pick(
         events.send(PopParcelFromStack {
             parcel_entry: entity,
             despawning: true,
+            aim: None,
+            power: 1.0,
         });
     }
 }
 
-fn maintain_parcel_stack(
+pub fn maintain_parcel_stack(
     parcel_stack: Query<&ParcelStack>,
     mut parcel_stack_entry: Query<&mut Transform>,
 ) {
@@ -247,10 +497,78 @@ fn get_parcel_stack_pos(index: usize) -> Vec3 {
     Vec3::Y * (index as f32 + 1.0) * config::PARCEL_SIZE * 0.95
 }
 
+fn upgrade_stack_capacity(score: Res<Score>, mut parcel_stack: Query<&mut ParcelStack>) {
+    let mut stack = parcel_stack.single_mut();
+
+    let earned = config::PLAYER_STACK_CAPACITY
+        + (score.score.max(0) / config::PLAYER_STACK_UPGRADE_SCORE) as usize;
+    let capacity = earned.min(config::PLAYER_MAX_STACK_CAPACITY);
+
+    // upgrades are kept even if the score drops again
+    if capacity > stack.capacity {
+        stack.capacity = capacity;
+    }
+}
+
+fn balance_parcel_stack(
+    time: Res<Time>,
+    mut events: EventWriter<PopParcelFromStack>,
+    player: Query<&Transform, (With<Player>, Without<ParcelStack>)>,
+    mut parcel_stack: Query<(&ParcelStack, &mut StackBalance, &mut Transform)>,
+) {
+    let dt = time.delta_seconds();
+    let position = player.single().translation;
+    let (stack, mut balance, mut transform) = parcel_stack.single_mut();
+
+    if dt <= 0.0 {
+        return;
+    }
+
+    let velocity = balance
+        .last_position
+        .map_or(Vec3::ZERO, |last| (position - last) / dt);
+    let velocity_change = velocity - balance.last_velocity;
+    balance.last_position = Some(position);
+    balance.last_velocity = velocity;
+
+    // a single parcel sits steady, every parcel above it makes the stack sway more
+    let height = stack.parcels_entries.len().saturating_sub(1) as f32;
+    let kick = -Vec2::new(velocity_change.x, velocity_change.z) * config::STACK_SWAY * height;
+    let spring =
+        -balance.tilt * config::STACK_STIFFNESS - balance.tilt_velocity * config::STACK_DAMPING;
+
+    balance.tilt_velocity += kick + spring * dt;
+    let tilt_velocity = balance.tilt_velocity;
+    balance.tilt += tilt_velocity * dt;
+
+    if balance.tilt.length() >= config::STACK_TOPPLE_ANGLE.to_radians() {
+        let direction = Vec3::new(balance.tilt.x, 0.0, balance.tilt.y).normalize();
+
+        for (i, entry) in stack.parcels_entries.iter().enumerate() {
+            events.send(PopParcelFromStack {
+                parcel_entry: *entry,
+                despawning: false,
+                aim: Some(position + direction * (i + 1) as f32),
+                power: config::PLAYER_MIN_THROW_POWER,
+            });
+        }
+
+        balance.tilt = Vec2::ZERO;
+        balance.tilt_velocity = Vec2::ZERO;
+    }
+
+    let lean = Vec3::new(balance.tilt.x, 0.0, balance.tilt.y);
+    transform.rotation = if lean.length() > 0.0 {
+        Quat::from_axis_angle(Vec3::Y.cross(lean).normalize(), lean.length())
+    } else {
+        Quat::IDENTITY
+    };
+}
+
 #[derive(Resource)]
 pub struct ClosestParcel(pub Option<Entity>);
 
-fn parcel_awarness(
+pub fn parcel_awarness(
     player: Query<&Transform, With<Player>>,
     mut closest_parcel: ResMut<ClosestParcel>,
     mut parcels: Query<&mut OutlineVolume, (With<Parcel>, Without<Picked>, Without<Despawn>)>,
@@ -292,39 +610,76 @@ fn parcel_awarness(
     }
 }
 
-fn pop_parcel(
+pub fn pop_parcel(
     mut events: EventWriter<PopParcelFromStack>,
-    mouse: Res<Input<MouseButton>>,
+    actions: Res<PlayerActions>,
     parcel_stack: Query<&ParcelStack>,
+    player: Query<&Transform, With<Player>>,
 ) {
-    if mouse.just_pressed(MouseButton::Left) {
-        let stack = parcel_stack.single();
+    let stack = parcel_stack.single();
+
+    if actions.throw_all {
+        let origin = player.single().translation;
+        let count = stack.parcels_entries.len();
+
+        for (i, entry) in stack.parcels_entries.iter().enumerate() {
+            let angle = (i as f32 - (count - 1) as f32 / 2.0) * config::PLAYER_FAN_ANGLE;
+            let aim = actions
+                .aim
+                .map(|aim| origin + Quat::from_rotation_y(angle.to_radians()) * (aim - origin));
+
+            events.send(PopParcelFromStack {
+                parcel_entry: *entry,
+                despawning: false,
+                aim,
+                power: actions.power,
+            });
+        }
+    } else if actions.throw {
+        // an empty slot throws the top parcel rather than nothing
+        let entry = actions
+            .slot
+            .and_then(|slot| stack.parcels_entries.get(slot))
+            .or_else(|| stack.parcels_entries.last());
+
+        if let Some(entry) = entry {
+            events.send(PopParcelFromStack {
+                parcel_entry: *entry,
+                despawning: false,
+                aim: actions.aim,
+                power: actions.power,
+            });
+        }
+    }
 
-        if let Some(entry) = stack.parcels_entries.last() {
+    if actions.drop_bottom {
+        if let Some(entry) = stack.parcels_entries.first() {
             events.send(PopParcelFromStack {
                 parcel_entry: *entry,
                 despawning: false,
+                aim: None,
+                power: 1.0,
             });
         }
     }
 }
 
-fn pickup_parcel(
+pub fn pickup_parcel(
     mut events: EventWriter<AddParcelToStack>,
-    keyboard_input: Res<Input<KeyCode>>,
+    actions: Res<PlayerActions>,
     mut closest_parcel: ResMut<ClosestParcel>,
     parcel_stack: Query<&ParcelStack>,
 ) {
     let stack = parcel_stack.single();
     if let Some(entity) = closest_parcel.0 {
-        if keyboard_input.just_pressed(KeyCode::E) && stack.parcels_entries.len() < 3 {
+        if actions.pickup && stack.parcels_entries.len() < stack.capacity {
             closest_parcel.0 = None;
             events.send(AddParcelToStack { parcel: entity });
         }
     }
 }
 
-fn remove_outline_from_picked(mut query: Query<&mut OutlineVolume, With<Picked>>) {
+pub fn remove_outline_from_picked(mut query: Query<&mut OutlineVolume, With<Picked>>) {
     for mut volume in query.iter_mut() {
         volume.visible = false;
     }
@@ -370,8 +725,8 @@ pub fn ray_from_mouse_position(
 ) -> (Vec3, Vec3) {
     let mouse_position = window.cursor_position().unwrap_or(Vec2::new(0.0, 0.0));
 
-    let x = 2.0 * (mouse_position.x / window.width() as f32) - 1.0;
-    let y = 2.0 * (mouse_position.y / window.height() as f32) - 1.0;
+    let x = 2.0 * (mouse_position.x / window.width()) - 1.0;
+    let y = 2.0 * (mouse_position.y / window.height()) - 1.0;
 
     let camera_inverse_matrix =
         camera_transform.compute_matrix() * camera.projection_matrix().inverse();
@@ -384,49 +739,248 @@ pub fn ray_from_mouse_position(
     (near, dir)
 }
 
-fn player_movement(
+pub fn player_input(
     keyboard_input: Res<Input<KeyCode>>,
+    mouse: Res<Input<MouseButton>>,
+    mouse_pos: Res<MousePosition>,
     time: Res<Time>,
-    _lines: ResMut<DebugLines>,
-    mut player: Query<&mut Transform, (With<Player>, Without<PlayerGfx>)>,
-    mut player_gfx: Query<&mut Transform, With<PlayerGfx>>,
+    mut charge: ResMut<ThrowCharge>,
+    mut selection: ResMut<StackSelection>,
+    mutators: Res<Mutators>,
+    mut actions: ResMut<PlayerActions>,
 ) {
-    let mut transform = player.single_mut();
-    let mut delta = Vec3::ZERO;
+    const SLOT_KEYS: [KeyCode; config::PLAYER_MAX_STACK_CAPACITY] = [
+        KeyCode::Key1,
+        KeyCode::Key2,
+        KeyCode::Key3,
+        KeyCode::Key4,
+        KeyCode::Key5,
+        KeyCode::Key6,
+    ];
+
+    let mut movement = Vec3::ZERO;
     if keyboard_input.pressed(KeyCode::W) {
-        delta -= Vec3::Z;
+        movement -= Vec3::Z;
     }
     if keyboard_input.pressed(KeyCode::S) {
-        delta += Vec3::Z;
+        movement += Vec3::Z;
     }
     if keyboard_input.pressed(KeyCode::A) {
-        delta -= Vec3::X;
+        movement -= Vec3::X;
     }
     if keyboard_input.pressed(KeyCode::D) {
-        delta += Vec3::X;
+        movement += Vec3::X;
+    }
+    if mutators.is_active(Mutator::MirroredControls) {
+        movement.x = -movement.x;
     }
 
-    delta = delta.normalize_or_zero() * config::PLAYER_SPEED * time.delta_seconds();
+    let mut throw = false;
+    let mut aim = mouse_pos.0;
+    let power = charge.power();
 
-    // check if new position is in bounds of ground
-    let new_pos = transform.translation + delta;
-    if new_pos.x <= -config::GROUND_SIZE / 2.0 - 0.5 || new_pos.x >= config::GROUND_SIZE / 2.0 + 0.5
-    {
-        delta.x = 0.0;
+    if mouse.pressed(MouseButton::Left) && !charge.fumbled {
+        charge.seconds += time.delta_seconds();
+
+        if charge.seconds >= config::PLAYER_THROW_FUMBLE_TIME {
+            // overcharged, drop the parcel at the player's feet
+            charge.fumbled = true;
+            throw = true;
+            aim = None;
+        }
     }
 
-    if new_pos.z <= -config::GROUND_SIZE / 2.0 - 0.5 || new_pos.z >= config::GROUND_SIZE / 2.0 + 0.5
+    if mouse.just_released(MouseButton::Left) {
+        throw = !charge.fumbled;
+        *charge = ThrowCharge::default();
+    }
+
+    if let Some(slot) = SLOT_KEYS
+        .iter()
+        .position(|key| keyboard_input.just_pressed(*key))
     {
-        delta.z = 0.0;
+        // picking the selected slot again goes back to throwing the top parcel
+        selection.0 = if selection.0 == Some(slot) {
+            None
+        } else {
+            Some(slot)
+        };
+    }
+
+    let slot = selection.0;
+    if throw {
+        selection.0 = None;
     }
 
+    actions.replace(PlayerActions {
+        movement,
+        pickup: keyboard_input.just_pressed(KeyCode::E),
+        throw,
+        aim,
+        power,
+        slot,
+        throw_all: mouse.just_pressed(MouseButton::Right),
+        drop_bottom: keyboard_input.just_pressed(KeyCode::F),
+        sprint: keyboard_input.pressed(KeyCode::LShift),
+        dash: keyboard_input.just_pressed(KeyCode::Space),
+    });
+}
+
+/// One-off actions are done once a step acted on them.
+fn clear_one_off_actions(mut actions: ResMut<PlayerActions>) {
+    actions.pickup = false;
+    actions.throw = false;
+    actions.throw_all = false;
+    actions.drop_bottom = false;
+    actions.dash = false;
+}
+
+pub fn player_movement(
+    actions: Res<PlayerActions>,
+    time: Res<Time>,
+    rapier_config: Res<RapierConfiguration>,
+    mut player: Query<
+        (
+            &mut KinematicCharacterController,
+            Option<&KinematicCharacterControllerOutput>,
+            &mut PlayerBody,
+            &mut Stamina,
+            &mut Dash,
+        ),
+        (With<Player>, Without<PlayerGfx>),
+    >,
+    mut player_gfx: Query<&mut Transform, With<PlayerGfx>>,
+    parcel_stack: Query<&ParcelStack>,
+) {
+    let (mut controller, output, mut body, mut stamina, mut dash) = player.single_mut();
+    let dt = time.delta_seconds();
+    let direction = actions.movement.normalize_or_zero();
+    let grounded = output.is_some_and(|output| output.grounded);
+
+    // every stacked parcel weighs the player down
+    let carried = parcel_stack.single().parcels_entries.len() as f32;
+    let carry_factor =
+        (1.0 - carried * config::PLAYER_CARRY_SLOWDOWN).max(config::PLAYER_MIN_CARRY_FACTOR);
+
+    let sprinting = actions.sprint && direction != Vec3::ZERO && stamina.stamina > 0.0;
+    if sprinting {
+        stamina.stamina = (stamina.stamina - config::PLAYER_SPRINT_DRAIN * dt).max(0.0);
+    } else {
+        stamina.stamina =
+            (stamina.stamina + config::PLAYER_STAMINA_REGEN * dt).min(config::PLAYER_MAX_STAMINA);
+    }
+
+    dash.cooldown = (dash.cooldown - dt).max(0.0);
+    dash.remaining = (dash.remaining - dt).max(0.0);
+    if actions.dash && dash.cooldown <= 0.0 && direction != Vec3::ZERO && grounded {
+        dash.remaining = config::PLAYER_DASH_TIME;
+        dash.cooldown = config::PLAYER_DASH_COOLDOWN;
+        dash.direction = direction;
+    }
+
+    if dash.is_dashing() {
+        body.velocity = dash.direction * config::PLAYER_DASH_SPEED * carry_factor;
+    } else {
+        let speed = if sprinting {
+            config::PLAYER_SPEED * config::PLAYER_SPRINT_FACTOR
+        } else {
+            config::PLAYER_SPEED
+        };
+        let wanted = direction * speed * carry_factor;
+
+        // ease towards the wanted velocity, stopping quicker than starting and barely
+        // steering in the air
+        let mut rate = if wanted == Vec3::ZERO {
+            config::PLAYER_DECELERATION
+        } else {
+            config::PLAYER_ACCELERATION
+        };
+        if !grounded {
+            rate *= config::PLAYER_AIR_CONTROL;
+        }
+        body.velocity = body.velocity.lerp(wanted, 1.0 - (-rate * dt).exp());
+    }
+
+    if grounded {
+        // keeps pressing into the ground so slopes and steps down stay grounded
+        body.vertical_speed = rapier_config.gravity.y * dt;
+    } else {
+        body.vertical_speed += rapier_config.gravity.y * dt;
+    }
+
+    controller.translation = Some((body.velocity + Vec3::Y * body.vertical_speed) * dt);
+
     let mut gfx_transform = player_gfx.single_mut();
 
-    if delta.x > 0.0 {
+    if direction.x > 0.0 {
         gfx_transform.rotation = Quat::from_rotation_y(180.0_f32.to_radians());
-    } else if delta.x < 0.0 {
+    } else if direction.x < 0.0 {
         gfx_transform.rotation = Quat::from_rotation_y(0.0_f32.to_radians());
     }
+}
 
-    transform.translation += delta;
+/// Puts a player that fell off the level back in the middle, losing the stacked parcels.
+fn respawn_fallen_player(
+    mut events: EventWriter<PopParcelFromStack>,
+    mut player: Query<
+        (
+            &mut Transform,
+            &mut PlayerBody,
+            &mut KinematicCharacterController,
+        ),
+        With<Player>,
+    >,
+    mut parcel_stack: Query<(&ParcelStack, &mut StackBalance)>,
+) {
+    let (mut transform, mut body, mut controller) = player.single_mut();
+    if transform.translation.y > config::PLAYER_RESPAWN_HEIGHT {
+        return;
+    }
+
+    let (stack, mut balance) = parcel_stack.single_mut();
+    for entry in &stack.parcels_entries {
+        events.send(PopParcelFromStack {
+            parcel_entry: *entry,
+            despawning: false,
+            aim: None,
+            power: 1.0,
+        });
+    }
+
+    // the teleport is no movement the stack should sway from
+    *balance = StackBalance::default();
+    *body = PlayerBody::default();
+    // the controller would move from where the collider fell to, not from the respawn point
+    controller.translation = None;
+    transform.translation = Vec3::ZERO;
+}
+
+/// Knocks the free parcels in the way of a dashing player aside.
+fn dash_shove_parcels(
+    player: Query<(&Transform, &Dash), With<Player>>,
+    tree: Res<ParcelsSpatialTree>,
+    mut parcels: Query<&mut Velocity, (With<Parcel>, Without<Picked>, Without<Despawn>)>,
+) {
+    let (transform, dash) = player.single();
+    if !dash.is_dashing() {
+        return;
+    }
+
+    for (position, entity) in
+        tree.within_distance(transform.translation, config::PLAYER_DASH_RADIUS)
+    {
+        let Ok(mut velocity) = parcels.get_mut(entity) else {
+            continue;
+        };
+
+        // push away from the player's path, forwards along the dash
+        let away = (position - transform.translation)
+            .reject_from(Vec3::Y)
+            .normalize_or_zero();
+        let shove = (dash.direction + away).normalize_or_zero() * config::PLAYER_DASH_SHOVE;
+
+        if velocity.linvel.dot(shove) < shove.length_squared() {
+            velocity.linvel = shove + Vec3::Y * config::PLAYER_DASH_SHOVE / 2.0;
+        }
+    }
 }
@@ -0,0 +1,323 @@
+use std::collections::HashMap;
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    on_fixed_update, parcel_awarness, play_spawn_timeline, player_movement, prelude::*,
+    rand_parcel_spawn, spawn_parcels, AgentServiceCode, ClosestParcel, Despawn, FixedAppExt,
+    GameRng, Interpolated, Parcel, ParcelSpawner, Picked, Player, ShippingArea,
+};
+
+pub struct PowerUpsPlugin;
+
+impl Plugin for PowerUpsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActivePowerUps>();
+        app.init_resource::<TimeScale>();
+        app.init_resource::<PowerUpAssets>();
+        app.add_system_set(SystemSet::on_enter(GameState::Ready).with_system(reset_power_ups))
+            .add_fixed_system_set(
+                on_fixed_update(GameState::Ready)
+                    // draws from the `GameRng` in the same order every round
+                    .with_system(spawn_power_ups.after(play_spawn_timeline))
+                    .with_system(tick_power_ups)
+                    .with_system(collect_power_ups.after(tick_power_ups))
+                    // the time scale of the step is set before anything moves
+                    .with_system(
+                        apply_slow_motion
+                            .after(collect_power_ups)
+                            .before(spawn_parcels)
+                            .before(player_movement),
+                    )
+                    .with_system(apply_magnet.after(collect_power_ups))
+                    .with_system(
+                        apply_scanner
+                            .after(collect_power_ups)
+                            .after(parcel_awarness),
+                    ),
+            );
+    }
+}
+
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PowerUpKind {
+    /// Pulls free parcels towards the shipping area of their carrier.
+    Magnet,
+    /// Slows down parcel spawning and physics, the player keeps full speed.
+    SlowMotion,
+    /// Doubles the points of correctly sorted parcels.
+    DoublePoints,
+    /// Outlines every parcel with the color of its carrier.
+    Scanner,
+}
+
+impl PowerUpKind {
+    pub const ALL: [PowerUpKind; 4] = [
+        PowerUpKind::Magnet,
+        PowerUpKind::SlowMotion,
+        PowerUpKind::DoublePoints,
+        PowerUpKind::Scanner,
+    ];
+
+    pub fn duration(&self) -> f32 {
+        match self {
+            PowerUpKind::Magnet => config::MAGNET_DURATION,
+            PowerUpKind::SlowMotion => config::SLOW_MOTION_DURATION,
+            PowerUpKind::DoublePoints => config::DOUBLE_POINTS_DURATION,
+            PowerUpKind::Scanner => config::SCANNER_DURATION,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            PowerUpKind::Magnet => Color::rgb(0.9, 0.2, 0.2),
+            PowerUpKind::SlowMotion => Color::rgb(0.3, 0.5, 1.0),
+            PowerUpKind::DoublePoints => Color::rgb(1.0, 0.85, 0.1),
+            PowerUpKind::Scanner => Color::rgb(0.2, 0.9, 0.4),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PowerUpKind::Magnet => "Magnet",
+            PowerUpKind::SlowMotion => "Slow Motion",
+            PowerUpKind::DoublePoints => "Double Points",
+            PowerUpKind::Scanner => "Scanner",
+        }
+    }
+}
+
+/// Collectible lying in the warehouse until picked up or expired.
+#[derive(Component)]
+pub struct PowerUp {
+    pub lifetime: Timer,
+}
+
+pub struct ActivePowerUp {
+    pub kind: PowerUpKind,
+    pub timer: Timer,
+}
+
+/// Power-ups in effect, collecting one that is already active restarts its timer.
+#[derive(Resource, Default)]
+pub struct ActivePowerUps(pub Vec<ActivePowerUp>);
+
+impl ActivePowerUps {
+    pub fn is_active(&self, kind: PowerUpKind) -> bool {
+        self.0.iter().any(|active| active.kind == kind)
+    }
+
+    pub fn activate(&mut self, kind: PowerUpKind) {
+        let timer = Timer::from_seconds(kind.duration(), TimerMode::Once);
+        match self.0.iter_mut().find(|active| active.kind == kind) {
+            Some(active) => active.timer = timer,
+            None => self.0.push(ActivePowerUp { kind, timer }),
+        }
+    }
+}
+
+/// Mesh shared by every power-up and a material per kind.
+#[derive(Resource)]
+pub struct PowerUpAssets {
+    pub mesh: Handle<Mesh>,
+    pub materials: HashMap<PowerUpKind, Handle<StandardMaterial>>,
+}
+
+impl FromWorld for PowerUpAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mesh = world
+            .resource_mut::<Assets<Mesh>>()
+            .add(Mesh::from(shape::UVSphere {
+                radius: config::POWER_UP_SIZE / 2.0,
+                ..default()
+            }));
+
+        let mut materials = world.resource_mut::<Assets<StandardMaterial>>();
+        let materials = PowerUpKind::ALL
+            .into_iter()
+            .map(|kind| {
+                let material = materials.add(StandardMaterial {
+                    base_color: kind.color(),
+                    emissive: kind.color(),
+                    ..default()
+                });
+
+                (kind, material)
+            })
+            .collect();
+
+        Self { mesh, materials }
+    }
+}
+
+/// Multiplier of the time parcel spawning and physics advance with.
+#[derive(Resource)]
+pub struct TimeScale(pub f32);
+
+impl Default for TimeScale {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+fn reset_power_ups(mut active: ResMut<ActivePowerUps>) {
+    active.0.clear();
+}
+
+fn spawn_power_ups(
+    mut commands: Commands,
+    spawner: Res<ParcelSpawner>,
+    mut rng: ResMut<GameRng>,
+    assets: Res<PowerUpAssets>,
+) {
+    // power-ups only ever drop together with a parcel
+    if !spawner.timer.just_finished() || !rng.0.gen_bool(config::POWER_UP_CHANCE) {
+        return;
+    }
+
+    let kind = PowerUpKind::ALL[rng.0.gen_range(0..PowerUpKind::ALL.len())];
+    let position = rand_parcel_spawn(&mut rng.0);
+
+    spawn_power_up(&mut commands, &assets, kind, position, spawner.parent);
+}
+
+/// Spawns a power-up of `kind` at `position` as a child of `parent`.
+pub fn spawn_power_up(
+    commands: &mut Commands,
+    assets: &PowerUpAssets,
+    kind: PowerUpKind,
+    position: Vec3,
+    parent: Entity,
+) -> Entity {
+    commands
+        .spawn((
+            PbrBundle {
+                mesh: assets.mesh.clone(),
+                material: assets.materials[&kind].clone(),
+                transform: Transform::from_translation(position),
+                ..default()
+            },
+            kind,
+            PowerUp {
+                lifetime: Timer::from_seconds(config::POWER_UP_LIFETIME, TimerMode::Once),
+            },
+            RigidBody::Dynamic,
+            Velocity::zero(),
+            Collider::ball(config::POWER_UP_SIZE / 2.0),
+            Interpolated::default(),
+            Name::new(format!("Power-up {}", kind.name())),
+        ))
+        .set_parent(parent)
+        .id()
+}
+
+pub fn collect_power_ups(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut active: ResMut<ActivePowerUps>,
+    player: Query<&Transform, With<Player>>,
+    mut power_ups: Query<(Entity, &GlobalTransform, &PowerUpKind, &mut PowerUp)>,
+) {
+    const DESPAWN_HEIGHT: f32 = -30.0;
+
+    let player = player.single().translation;
+
+    for (entity, transform, kind, mut power_up) in power_ups.iter_mut() {
+        power_up.lifetime.tick(time.delta());
+
+        let position = transform.translation();
+        if position.distance(player) <= config::POWER_UP_PICKUP_RADIUS {
+            active.activate(*kind);
+            commands.entity(entity).despawn_recursive();
+        } else if power_up.lifetime.finished() || position.y <= DESPAWN_HEIGHT {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
+
+fn tick_power_ups(time: Res<Time>, mut active: ResMut<ActivePowerUps>) {
+    for power_up in active.0.iter_mut() {
+        power_up.timer.tick(time.delta());
+    }
+
+    active.0.retain(|power_up| !power_up.timer.finished());
+}
+
+pub fn apply_slow_motion(
+    active: Res<ActivePowerUps>,
+    mut time_scale: ResMut<TimeScale>,
+    mut rapier_config: ResMut<RapierConfiguration>,
+) {
+    let scale = if active.is_active(PowerUpKind::SlowMotion) {
+        config::SLOW_MOTION_SCALE
+    } else {
+        1.0
+    };
+
+    if time_scale.0 == scale {
+        return;
+    }
+
+    time_scale.0 = scale;
+    if let TimestepMode::Variable { time_scale, .. }
+    | TimestepMode::Interpolated { time_scale, .. } = &mut rapier_config.timestep_mode
+    {
+        *time_scale = scale;
+    }
+}
+
+pub fn apply_magnet(
+    time: Res<Time>,
+    active: Res<ActivePowerUps>,
+    shipping_areas: Query<(&Transform, &AgentServiceCode), With<ShippingArea>>,
+    mut parcels: Query<
+        (&GlobalTransform, &AgentServiceCode, &mut Velocity),
+        (With<Parcel>, Without<Picked>, Without<Despawn>),
+    >,
+) {
+    if !active.is_active(PowerUpKind::Magnet) {
+        return;
+    }
+
+    for (transform, code, mut velocity) in parcels.iter_mut() {
+        let Some((area, _)) = shipping_areas.iter().find(|(_, c)| *c == code) else {
+            continue;
+        };
+
+        let pull = (area.translation - transform.translation())
+            .reject_from(Vec3::Y)
+            .normalize_or_zero();
+        velocity.linvel += pull * config::MAGNET_STRENGTH * time.delta_seconds();
+    }
+}
+
+pub fn apply_scanner(
+    active: Res<ActivePowerUps>,
+    closest_parcel: Res<ClosestParcel>,
+    mut scanning: Local<bool>,
+    mut parcels: Query<
+        (Entity, &AgentServiceCode, &mut OutlineVolume),
+        (With<Parcel>, Without<Picked>, Without<Despawn>),
+    >,
+) {
+    let active = active.is_active(PowerUpKind::Scanner);
+    if !active && !*scanning {
+        return;
+    }
+
+    for (entity, code, mut outline) in parcels.iter_mut() {
+        let closest = closest_parcel.0 == Some(entity);
+
+        // the parcel a pickup would stack stays outlined in white
+        if active && !closest {
+            outline.visible = true;
+            outline.colour = code.color();
+        } else {
+            outline.visible = closest;
+            outline.colour = Color::rgba(1.0, 1.0, 1.0, 0.8);
+        }
+    }
+
+    *scanning = active;
+}
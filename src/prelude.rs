@@ -10,12 +10,52 @@ pub use bevy_sprite3d::*;
 pub use bevy_tweening::*;
 
 pub mod config {
+    /// Seconds simulated by every gameplay and physics step.
+    pub const FIXED_TIMESTEP: f32 = 1.0 / 60.0;
+
     pub const GROUND_SIZE: f32 = 12.0;
+    /// Height of the top of the warehouse floor.
+    pub const FLOOR_HEIGHT: f32 = -0.5;
     pub const GROUND_DEPTH: f32 = 1.0;
 
     pub const PLAYER_SPEED: f32 = 11.0;
+    pub const PLAYER_ACCELERATION: f32 = 12.0;
+    pub const PLAYER_DECELERATION: f32 = 18.0;
+    pub const PLAYER_AIR_CONTROL: f32 = 0.2;
+    pub const PLAYER_STEP_HEIGHT: f32 = 0.3;
+    pub const PLAYER_MAX_SLOPE: f32 = 45.0;
+    pub const PLAYER_RESPAWN_HEIGHT: f32 = -15.0;
     pub const PLAYER_THROW_FACTOR: f32 = 1.0;
     pub const PLAYER_MAX_THROW_MAQ: f32 = 12.0;
+    pub const PLAYER_MIN_THROW_MAQ: f32 = 2.0;
+    pub const PLAYER_THROW_LIFT: f32 = 7.0;
+    pub const PLAYER_AIM_ASSIST_ANGLE: f32 = 30.0;
+    pub const PLAYER_THROW_CHARGE_TIME: f32 = 1.0;
+    pub const PLAYER_THROW_FUMBLE_TIME: f32 = 1.75;
+    pub const PLAYER_MIN_THROW_POWER: f32 = 0.4;
+    pub const PLAYER_MAX_THROW_POWER: f32 = 1.3;
+    pub const PLAYER_FAN_ANGLE: f32 = 15.0;
+
+    pub const PLAYER_MAX_STAMINA: f32 = 100.0;
+    pub const PLAYER_SPRINT_FACTOR: f32 = 1.5;
+    pub const PLAYER_SPRINT_DRAIN: f32 = 40.0;
+    pub const PLAYER_STAMINA_REGEN: f32 = 20.0;
+    pub const PLAYER_DASH_SPEED: f32 = 24.0;
+    pub const PLAYER_DASH_TIME: f32 = 0.15;
+    pub const PLAYER_DASH_COOLDOWN: f32 = 1.5;
+    pub const PLAYER_DASH_RADIUS: f32 = 1.2;
+    pub const PLAYER_DASH_SHOVE: f32 = 8.0;
+    pub const PLAYER_CARRY_SLOWDOWN: f32 = 0.06;
+    pub const PLAYER_MIN_CARRY_FACTOR: f32 = 0.6;
+
+    pub const PLAYER_STACK_CAPACITY: usize = 3;
+    pub const PLAYER_MAX_STACK_CAPACITY: usize = 6;
+    pub const PLAYER_STACK_UPGRADE_SCORE: i32 = 10;
+
+    pub const STACK_SWAY: f32 = 0.05;
+    pub const STACK_STIFFNESS: f32 = 36.0;
+    pub const STACK_DAMPING: f32 = 4.8;
+    pub const STACK_TOPPLE_ANGLE: f32 = 15.0;
 
     pub const PARCEL_SPAWN_RATE: u64 = 3000;
     pub const PARCEL_SPAWN_Y: f32 = 14.0;
@@ -25,10 +65,52 @@ pub mod config {
     pub const PARCEL_MIN_LINVEL_Z: f32 = 0.2;
     pub const PARCEL_MAX_ANGVEL: f32 = 0.0;
     pub const PARCEL_SIZE: f32 = 1.0;
-
-    pub const PARCEL_LEVEL_UP: u64 = 3;
-    pub const PARCEL_LEVEL_UP_DECR: u64 = 350;
-    pub const PARCEL_LEVEL_UP_MIN: u64 = 2300;
+    /// Parcels of every kind created ahead of the round.
+    pub const PARCEL_POOL_SIZE: usize = 16;
 
     pub const GAME_TIME: f32 = 128.0;
+    pub const ENDLESS_MAX_MISSES: u32 = 10;
+    /// Seconds after which a tournament round is stopped, e.g. Zen rounds never end.
+    pub const TOURNAMENT_MAX_SECONDS: f32 = 600.0;
+    pub const HIGH_SCORE_ENTRIES: usize = 5;
+    /// Fixed steps between two recorded positions of the ghost run.
+    pub const GHOST_SAMPLE_STEPS: u32 = 6;
+    pub const GHOST_ALPHA: f32 = 0.35;
+
+    pub const POWER_UP_CHANCE: f64 = 0.15;
+    pub const POWER_UP_LIFETIME: f32 = 12.0;
+    pub const POWER_UP_PICKUP_RADIUS: f32 = 1.2;
+    pub const POWER_UP_SIZE: f32 = 0.6;
+    pub const MAGNET_DURATION: f32 = 6.0;
+    pub const MAGNET_STRENGTH: f32 = 4.0;
+    pub const SLOW_MOTION_DURATION: f32 = 8.0;
+    pub const SLOW_MOTION_SCALE: f32 = 0.5;
+    pub const DOUBLE_POINTS_DURATION: f32 = 10.0;
+    pub const SCANNER_DURATION: f32 = 12.0;
+
+    pub const CAREFUL_SORTER_DELIVERIES: u32 = 20;
+    pub const LONG_SHOT_DISTANCE: f32 = 10.0;
+
+    pub const LOW_GRAVITY_FACTOR: f32 = 0.4;
+    pub const SLIPPERY_PARCEL_FRICTION: f32 = 0.05;
+    pub const GIANT_PARCEL_SCALE: f32 = 1.6;
+
+    pub const BOT_VIEW_RADIUS: f32 = 20.0;
+
+    /// Window sizes to pick from in the settings, the first one is the default.
+    pub const RESOLUTIONS: [(f32, f32); 4] = [
+        (1280.0, 720.0),
+        (1600.0, 900.0),
+        (1920.0, 1080.0),
+        (2560.0, 1440.0),
+    ];
+    pub const VOLUME_STEP: f32 = 0.1;
+
+    /// Trauma added by a lost or mis-sorted parcel, the camera shakes with its square.
+    pub const CAMERA_SHAKE_TRAUMA: f32 = 0.5;
+    /// Trauma lost every second.
+    pub const CAMERA_SHAKE_DECAY: f32 = 1.5;
+    /// Offset of the camera at full trauma.
+    pub const CAMERA_SHAKE_OFFSET: f32 = 0.4;
+    pub const CAMERA_SHAKE_FREQUENCY: f32 = 25.0;
 }
@@ -0,0 +1,221 @@
+//! Rounds recorded as their rules and the [`PlayerActions`] of every fixed step, the simulation
+//! repeats a round from the same seed and actions.
+
+use std::path::{Path, PathBuf};
+
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    on_fixed_update, prelude::*, DailyChallenge, Difficulty, FixedAppExt, FixedStage, GameMode,
+    GameRng, Mutators, PlayerActions, SpawnTimeline,
+};
+
+/// Bumped whenever the format changes, replays of other versions are not played.
+pub const REPLAY_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    /// Seeds the [`GameRng`] of the round.
+    pub seed: u64,
+    pub mode: GameMode,
+    pub difficulty: Difficulty,
+    pub mutators: Mutators,
+    pub timeline: SpawnTimeline,
+    /// Actions of every fixed step, the round ends after the last one.
+    pub actions: Vec<PlayerActions>,
+}
+
+impl Replay {
+    pub fn read(path: &Path) -> Result<Self, String> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|err| format!("could not read {}: {err}", path.display()))?;
+        let replay: Self = serde_json::from_str(&contents)
+            .map_err(|err| format!("{} is not a replay: {err}", path.display()))?;
+
+        if replay.version != REPLAY_VERSION {
+            return Err(format!(
+                "{} is a replay of version {}, expected {REPLAY_VERSION}",
+                path.display(),
+                replay.version
+            ));
+        }
+        replay
+            .timeline
+            .validate()
+            .map_err(|err| format!("{} is not a replay: {err}", path.display()))?;
+
+        Ok(replay)
+    }
+
+    /// Writes the replay to `path`, failures are only logged.
+    pub fn write(&self, path: &Path) {
+        let result = serde_json::to_string(self)
+            .map_err(std::io::Error::from)
+            .and_then(|contents| std::fs::write(path, contents));
+
+        match result {
+            Ok(()) => log::info!("replay written to {}", path.display()),
+            Err(err) => log::warn!("could not write replay to {}: {err}", path.display()),
+        }
+    }
+
+    /// Sets up the rules of the next round as they were.
+    pub fn apply(&self, world: &mut World) {
+        world.insert_resource(GameRng::from_seed(self.seed));
+        world.insert_resource(self.mode);
+        world.insert_resource(self.difficulty);
+        world.insert_resource(self.mutators.clone());
+        world.insert_resource(self.timeline.clone());
+        world.remove_resource::<DailyChallenge>();
+    }
+}
+
+/// Records the rounds and writes the last one to the given path once it's over.
+pub struct ReplayRecorderPlugin(pub PathBuf);
+
+impl Plugin for ReplayRecorderPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ReplayRecorder {
+            path: self.0.clone(),
+            replay: None,
+        });
+        app.add_system_set(SystemSet::on_enter(GameState::Ready).with_system(start_recording))
+            .add_fixed_system_set_to_stage(
+                FixedStage::PreUpdate,
+                on_fixed_update(GameState::Ready).with_system(record_actions),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(write_recording));
+    }
+}
+
+#[derive(Resource)]
+pub struct ReplayRecorder {
+    path: PathBuf,
+    /// Replay of the ongoing round.
+    pub replay: Option<Replay>,
+}
+
+fn start_recording(
+    mut recorder: ResMut<ReplayRecorder>,
+    mut rng: ResMut<GameRng>,
+    mode: Res<GameMode>,
+    difficulty: Res<Difficulty>,
+    mutators: Res<Mutators>,
+    timeline: Res<SpawnTimeline>,
+) {
+    // a seed the replay can start over from, nothing draws from the rng before the first step
+    let seed = rng.0.gen();
+    *rng = GameRng::from_seed(seed);
+
+    recorder.replay = Some(Replay {
+        version: REPLAY_VERSION,
+        seed,
+        mode: *mode,
+        difficulty: *difficulty,
+        mutators: mutators.clone(),
+        timeline: timeline.clone(),
+        actions: vec![],
+    });
+}
+
+fn record_actions(mut recorder: ResMut<ReplayRecorder>, actions: Res<PlayerActions>) {
+    if let Some(replay) = &mut recorder.replay {
+        replay.actions.push(actions.clone());
+    }
+}
+
+fn write_recording(mut recorder: ResMut<ReplayRecorder>) {
+    if let Some(replay) = recorder.replay.take() {
+        replay.write(&recorder.path);
+    }
+}
+
+/// Plays a [`Replay`] instead of the player, starting right away in the headless app or
+/// skipping the menu of the windowed game. Only the first round is played back.
+pub struct ReplayPlaybackPlugin(pub Replay);
+
+impl Plugin for ReplayPlaybackPlugin {
+    fn build(&self, app: &mut App) {
+        self.0.apply(&mut app.world);
+        app.insert_resource(ReplayPlayback {
+            replay: self.0.clone(),
+            played: false,
+            step: None,
+        });
+        app.add_system_set(SystemSet::on_update(GameState::Menu).with_system(start_replay))
+            .add_system_set(SystemSet::on_enter(GameState::Ready).with_system(start_playback))
+            .add_fixed_system_set_to_stage(
+                FixedStage::PreUpdate,
+                on_fixed_update(GameState::Ready).with_system(play_actions),
+            )
+            .add_fixed_system_set_to_stage(
+                FixedStage::PostUpdate,
+                on_fixed_update(GameState::Ready).with_system(end_replay),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(stop_playback));
+    }
+}
+
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    played: bool,
+    /// Next step of the replay, `None` while no replay is played.
+    pub step: Option<usize>,
+}
+
+fn start_replay(world: &mut World) {
+    let played = world.resource::<ReplayPlayback>().played;
+    // leaving the menu tears it down, it has to be set up first
+    let menu_ready = world
+        .query_filtered::<(), With<Camera2d>>()
+        .iter(world)
+        .next()
+        .is_some();
+    if played || !menu_ready {
+        return;
+    }
+
+    // in place right away, the round is set up as soon as the menu is left
+    let replay = world.resource::<ReplayPlayback>().replay.clone();
+    replay.apply(world);
+    world
+        .resource_mut::<State<GameState>>()
+        .set(GameState::Ready)
+        .unwrap();
+}
+
+fn start_playback(mut playback: ResMut<ReplayPlayback>) {
+    if !playback.played {
+        playback.played = true;
+        playback.step = Some(0);
+    }
+}
+
+fn play_actions(mut playback: ResMut<ReplayPlayback>, mut actions: ResMut<PlayerActions>) {
+    let Some(step) = playback.step else {
+        return;
+    };
+
+    if let Some(recorded) = playback.replay.actions.get(step) {
+        *actions = recorded.clone();
+        playback.step = Some(step + 1);
+    }
+}
+
+/// Ends the round right after the last recorded step.
+fn end_replay(playback: Res<ReplayPlayback>, mut app_state: ResMut<State<GameState>>) {
+    if playback
+        .step
+        .is_some_and(|step| step >= playback.replay.actions.len())
+    {
+        // the round may have ended this step already
+        let _ = app_state.set(GameState::GameOver);
+    }
+}
+
+fn stop_playback(mut playback: ResMut<ReplayPlayback>) {
+    playback.step = None;
+}
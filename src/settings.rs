@@ -0,0 +1,362 @@
+//! Graphics, audio and gameplay options of the windowed game, changed on their own screen from
+//! the menu, applied right away and kept in the data directory.
+
+use bevy::{audio::AudioSink, window::PresentMode};
+use serde::{Deserialize, Serialize};
+
+use crate::{prelude::*, storage, FontAssets, Music};
+
+/// Opens the settings with S in the menu and applies them, only added to the windowed game.
+pub struct SettingsPlugin;
+
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Settings::load());
+        app.init_resource::<SelectedSetting>();
+        app.add_system_set(
+            SystemSet::on_enter(GameState::Settings).with_system(setup_settings_menu),
+        )
+        .add_system_set(
+            SystemSet::on_update(GameState::Settings)
+                .with_system(change_settings)
+                .with_system(update_settings_text.after(change_settings)),
+        )
+        .add_system_set(
+            SystemSet::on_exit(GameState::Settings)
+                .with_system(save_settings)
+                .with_system(cleanup_settings_menu),
+        )
+        .add_system(apply_window_settings)
+        .add_system(apply_shadow_settings)
+        .add_system(apply_volume_settings);
+    }
+}
+
+/// Kept in the data directory, options missing from the file keep their defaults.
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Size of the window, one of [`config::RESOLUTIONS`].
+    pub resolution: (f32, f32),
+    pub fullscreen: bool,
+    pub vsync: bool,
+    /// Shadows cast by the light of the warehouse.
+    pub shadows: bool,
+    /// Scales the music and sound effect volumes.
+    pub master_volume: f32,
+    pub music_volume: f32,
+    pub sfx_volume: f32,
+    /// Shakes the camera when a parcel is lost or mis-sorted.
+    pub camera_shake: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            resolution: config::RESOLUTIONS[0],
+            fullscreen: false,
+            vsync: false,
+            shadows: true,
+            master_volume: 1.0,
+            music_volume: 1.0,
+            sfx_volume: 1.0,
+            camera_shake: true,
+        }
+    }
+}
+
+impl Settings {
+    const FILE: &'static str = "settings.json";
+
+    pub fn load() -> Self {
+        storage::load(Self::FILE).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::FILE, self);
+    }
+
+    pub fn music(&self) -> f32 {
+        self.master_volume * self.music_volume
+    }
+
+    /// Volume sound effects are to be played at. There are none yet, new ones go through
+    /// `audio.play_with_settings` with `PlaybackSettings::ONCE.with_volume(settings.sfx())`.
+    pub fn sfx(&self) -> f32 {
+        self.master_volume * self.sfx_volume
+    }
+
+    pub fn present_mode(&self) -> PresentMode {
+        if self.vsync {
+            PresentMode::AutoVsync
+        } else {
+            PresentMode::AutoNoVsync
+        }
+    }
+
+    pub fn window_mode(&self) -> WindowMode {
+        if self.fullscreen {
+            WindowMode::BorderlessFullscreen
+        } else {
+            WindowMode::Windowed
+        }
+    }
+}
+
+/// Rows of the settings screen.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Setting {
+    Resolution,
+    Fullscreen,
+    Vsync,
+    Shadows,
+    MasterVolume,
+    MusicVolume,
+    SfxVolume,
+    CameraShake,
+}
+
+impl Setting {
+    pub const ALL: [Setting; 8] = [
+        Setting::Resolution,
+        Setting::Fullscreen,
+        Setting::Vsync,
+        Setting::Shadows,
+        Setting::MasterVolume,
+        Setting::MusicVolume,
+        Setting::SfxVolume,
+        Setting::CameraShake,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Setting::Resolution => "Resolution",
+            Setting::Fullscreen => "Fullscreen",
+            Setting::Vsync => "Vsync",
+            Setting::Shadows => "Shadows",
+            Setting::MasterVolume => "Master volume",
+            Setting::MusicVolume => "Music volume",
+            Setting::SfxVolume => "Sound effects volume",
+            Setting::CameraShake => "Camera shake",
+        }
+    }
+
+    /// Current value of the setting as shown on the screen.
+    pub fn describe(&self, settings: &Settings) -> String {
+        let on_off = |on: bool| if on { "On" } else { "Off" }.to_string();
+        let percent = |volume: f32| format!("{:.0}%", volume * 100.0);
+
+        match self {
+            Setting::Resolution => {
+                format!("{} x {}", settings.resolution.0, settings.resolution.1)
+            }
+            Setting::Fullscreen => on_off(settings.fullscreen),
+            Setting::Vsync => on_off(settings.vsync),
+            Setting::Shadows => on_off(settings.shadows),
+            Setting::MasterVolume => percent(settings.master_volume),
+            Setting::MusicVolume => percent(settings.music_volume),
+            Setting::SfxVolume => percent(settings.sfx_volume),
+            Setting::CameraShake => on_off(settings.camera_shake),
+        }
+    }
+
+    /// Steps the setting forwards for a positive `step` and backwards for a negative one,
+    /// toggles switch either way.
+    pub fn change(&self, settings: &mut Settings, step: i32) {
+        let volume = |volume: &mut f32| {
+            let steps = (*volume / config::VOLUME_STEP).round() as i32 + step;
+            *volume = (steps as f32 * config::VOLUME_STEP).clamp(0.0, 1.0);
+        };
+
+        match self {
+            Setting::Resolution => {
+                let count = config::RESOLUTIONS.len() as i32;
+                let current = config::RESOLUTIONS
+                    .iter()
+                    .position(|resolution| *resolution == settings.resolution)
+                    .unwrap_or(0) as i32;
+                settings.resolution =
+                    config::RESOLUTIONS[(current + step).rem_euclid(count) as usize];
+            }
+            Setting::Fullscreen => settings.fullscreen = !settings.fullscreen,
+            Setting::Vsync => settings.vsync = !settings.vsync,
+            Setting::Shadows => settings.shadows = !settings.shadows,
+            Setting::MasterVolume => volume(&mut settings.master_volume),
+            Setting::MusicVolume => volume(&mut settings.music_volume),
+            Setting::SfxVolume => volume(&mut settings.sfx_volume),
+            Setting::CameraShake => settings.camera_shake = !settings.camera_shake,
+        }
+    }
+}
+
+/// Row of the settings screen changed with Left and Right.
+#[derive(Resource, Default)]
+pub struct SelectedSetting(pub usize);
+
+#[derive(Component)]
+pub struct SettingsRoot;
+
+#[derive(Component)]
+pub struct SettingsText;
+
+fn setup_settings_menu(mut commands: Commands, font_assets: Res<FontAssets>) {
+    commands.spawn(Camera2dBundle::default());
+
+    let style = |font_size: f32, color: Color| TextStyle {
+        font: font_assets.montserrat.clone(),
+        font_size,
+        color,
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                margin: UiRect::all(Val::Auto),
+                align_items: AlignItems::Center,
+                flex_direction: FlexDirection::ColumnReverse,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .insert(SettingsRoot)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Settings",
+                style(60.0, Color::rgb(0.9, 0.9, 0.9)),
+            ));
+
+            parent.spawn((
+                TextBundle::from_sections(
+                    Setting::ALL.map(|_| TextSection::from_style(style(30.0, Color::WHITE))),
+                ),
+                SettingsText,
+            ));
+
+            parent.spawn(TextBundle::from_section(
+                "Up, Down to pick - Left, Right to change - Escape to go back",
+                style(20.0, Color::rgb(0.6, 0.6, 0.6)),
+            ));
+        });
+}
+
+fn change_settings(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut settings: ResMut<Settings>,
+    mut selected: ResMut<SelectedSetting>,
+    mut app_state: ResMut<State<GameState>>,
+) {
+    let count = Setting::ALL.len();
+
+    if keyboard_input.just_pressed(KeyCode::Up) {
+        selected.0 = (selected.0 + count - 1) % count;
+    } else if keyboard_input.just_pressed(KeyCode::Down) {
+        selected.0 = (selected.0 + 1) % count;
+    } else if keyboard_input.just_pressed(KeyCode::Left) {
+        Setting::ALL[selected.0].change(&mut settings, -1);
+    } else if keyboard_input.just_pressed(KeyCode::Right) {
+        Setting::ALL[selected.0].change(&mut settings, 1);
+    } else if keyboard_input.just_pressed(KeyCode::Escape) {
+        app_state.set(GameState::Menu).unwrap();
+    }
+}
+
+fn update_settings_text(
+    settings: Res<Settings>,
+    selected: Res<SelectedSetting>,
+    mut text: Query<&mut Text, With<SettingsText>>,
+) {
+    for mut text in &mut text {
+        for (i, (section, setting)) in text.sections.iter_mut().zip(Setting::ALL).enumerate() {
+            let (marker, color) = if i == selected.0 {
+                (">", Color::rgb(1.0, 0.85, 0.1))
+            } else {
+                (" ", Color::rgb(0.9, 0.9, 0.9))
+            };
+
+            section.value = format!(
+                "{marker} {}: {}\n",
+                setting.name(),
+                setting.describe(&settings)
+            );
+            section.style.color = color;
+        }
+    }
+}
+
+fn save_settings(settings: Res<Settings>) {
+    settings.save();
+}
+
+fn cleanup_settings_menu(
+    mut commands: Commands,
+    root: Query<Entity, With<SettingsRoot>>,
+    cam: Query<Entity, With<Camera2d>>,
+) {
+    commands.entity(root.single()).despawn_recursive();
+    commands.entity(cam.single()).despawn_recursive();
+}
+
+/// The window starts out with the settings and the command line, afterwards only the options
+/// changed since are applied, so e.g. a volume step keeps a `--width` given on the command line.
+fn apply_window_settings(
+    settings: Res<Settings>,
+    mut applied: Local<Option<Settings>>,
+    mut windows: ResMut<Windows>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    let Some(applied) = applied.replace(settings.clone()) else {
+        return;
+    };
+    let Some(window) = windows.get_primary_mut() else {
+        return;
+    };
+
+    if settings.vsync != applied.vsync {
+        window.set_present_mode(settings.present_mode());
+    }
+    if settings.fullscreen != applied.fullscreen {
+        window.set_mode(settings.window_mode());
+    }
+    let resized = settings.resolution != applied.resolution;
+    let left_fullscreen = applied.fullscreen && !settings.fullscreen;
+    if !settings.fullscreen && (resized || left_fullscreen) {
+        let (width, height) = settings.resolution;
+        window.set_resolution(width, height);
+    }
+}
+
+/// The light is spawned with every round, it's set up as soon as it's there.
+fn apply_shadow_settings(
+    settings: Res<Settings>,
+    mut lights: Query<&mut PointLight>,
+    added: Query<(), Added<PointLight>>,
+) {
+    if !settings.is_changed() && added.is_empty() {
+        return;
+    }
+
+    for mut light in &mut lights {
+        if light.shadows_enabled != settings.shadows {
+            light.shadows_enabled = settings.shadows;
+        }
+    }
+}
+
+fn apply_volume_settings(
+    settings: Res<Settings>,
+    music: Option<Res<Music>>,
+    sinks: Res<Assets<AudioSink>>,
+) {
+    let Some(music) = music else {
+        return;
+    };
+    if !settings.is_changed() {
+        return;
+    }
+
+    if let Some(sink) = sinks.get(&music.0) {
+        sink.set_volume(settings.music());
+    }
+}
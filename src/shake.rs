@@ -0,0 +1,62 @@
+use crate::{
+    on_fixed_update, prelude::*, FixedAppExt, FixedStage, ParcelDelivered, ParcelLost, Settings,
+};
+
+/// Shakes the camera when a parcel is lost or mis-sorted, unless turned off in the [`Settings`].
+/// Only added to the windowed game.
+pub struct CameraShakePlugin;
+
+impl Plugin for CameraShakePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_fixed_system_set_to_stage(
+            FixedStage::PostUpdate,
+            on_fixed_update(GameState::Ready).with_system(add_trauma),
+        )
+        .add_system_set(SystemSet::on_update(GameState::Ready).with_system(shake_camera));
+    }
+}
+
+/// Shake of the camera, which returns to where it started as the trauma wears off.
+#[derive(Component, Default)]
+pub struct CameraShake {
+    /// From `0.0` to `1.0`, the camera is offset by its square.
+    pub trauma: f32,
+    origin: Option<Vec3>,
+}
+
+fn add_trauma(
+    settings: Res<Settings>,
+    mut delivered: EventReader<ParcelDelivered>,
+    mut lost: EventReader<ParcelLost>,
+    mut cameras: Query<&mut CameraShake>,
+) {
+    let misses = delivered.iter().filter(|event| !event.is_correct()).count() + lost.iter().count();
+    if misses == 0 || !settings.camera_shake {
+        return;
+    }
+
+    for mut shake in &mut cameras {
+        shake.trauma = (shake.trauma + config::CAMERA_SHAKE_TRAUMA * misses as f32).min(1.0);
+    }
+}
+
+fn shake_camera(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    mut cameras: Query<(&mut Transform, &mut CameraShake)>,
+) {
+    for (mut transform, mut shake) in &mut cameras {
+        let origin = *shake.origin.get_or_insert(transform.translation);
+        if !settings.camera_shake {
+            shake.trauma = 0.0;
+        }
+        shake.trauma = (shake.trauma - config::CAMERA_SHAKE_DECAY * time.delta_seconds()).max(0.0);
+
+        // smooth noise from two detuned waves, the rng of the round is left alone
+        let t = time.elapsed_seconds() * config::CAMERA_SHAKE_FREQUENCY;
+        let offset = Vec3::new(t.sin(), (t * 1.3 + 1.7).sin(), 0.0)
+            * config::CAMERA_SHAKE_OFFSET
+            * shake.trauma.powi(2);
+        transform.translation = origin + transform.rotation * offset;
+    }
+}
@@ -0,0 +1,658 @@
+use std::cmp::Ordering;
+use std::time::Duration;
+
+use bevy::transform::transform_propagate_system;
+use bevy_rapier3d::plugin::systems::{init_colliders, init_rigid_bodies};
+use bevy_rapier3d::rapier::prelude::{ColliderSet, RigidBodyHandle};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    apply_gravity, prelude::*, rebuild_parcels, spawn_power_up, stack_parcel, storage,
+    update_spatial_tree, ActivePowerUp, ActivePowerUps, ActiveWave, AddParcelToStack,
+    AgentServiceCode, ClosestParcel, Dash, Despawn, Difficulty, GameMode, GameRng, Interpolated,
+    MoveTruck, Mutators, Parcel, ParcelCommands, ParcelKind, ParcelSpawner, ParcelStack,
+    ParcelStackEntry, Picked, Player, PlayerBody, Pooled, PopParcelFromStack, PowerUp,
+    PowerUpAssets, PowerUpKind, RoundStats, Score, ShippingArea, StackBalance, Stamina, Thrown,
+    TimeRemaining, Truck,
+};
+
+/// Saves the round with F5 and loads it back with F9, only added to the windowed game.
+pub struct SnapshotPlugin;
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_set(
+            SystemSet::on_update(GameState::Ready)
+                .with_system(quick_save)
+                .with_system(quick_load.after(quick_save)),
+        );
+    }
+}
+
+/// Bumped whenever the format changes, snapshots of other versions are not loaded.
+pub const SNAPSHOT_VERSION: u32 = 3;
+
+/// Everything needed to carry on with a round later, taken in between two fixed steps.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    /// The rules the round is played by, they replace those of the round it's restored into.
+    pub mode: GameMode,
+    pub difficulty: Difficulty,
+    pub mutators: Mutators,
+    pub rng: GameRng,
+    pub score: i32,
+    /// Fraction of a point the mutators left over, see [`Score::bonus`].
+    pub bonus: f32,
+    pub time_remaining: TimerSnapshot,
+    pub stats: RoundStats,
+    pub spawner: SpawnerSnapshot,
+    pub player: Vec3,
+    pub player_body: PlayerBody,
+    pub stamina: Stamina,
+    pub dash: Dash,
+    pub stack_capacity: usize,
+    pub stack_balance: StackBalance,
+    /// Stacked parcels come first, from the bottom of the stack up, the others by position.
+    pub parcels: Vec<ParcelSnapshot>,
+    pub shipping_areas: Vec<ShippingAreaSnapshot>,
+    pub active_power_ups: Vec<ActivePowerUpSnapshot>,
+    /// Power-ups waiting to be collected, by position.
+    pub power_ups: Vec<PowerUpSnapshot>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TimerSnapshot {
+    pub duration: Duration,
+    pub elapsed: Duration,
+}
+
+impl TimerSnapshot {
+    fn new(timer: &Timer) -> Self {
+        Self {
+            duration: timer.duration(),
+            elapsed: timer.elapsed(),
+        }
+    }
+
+    fn restore(&self, timer: &mut Timer) {
+        timer.reset();
+        timer.set_duration(self.duration);
+        timer.set_elapsed(self.elapsed);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpawnerSnapshot {
+    pub count: u64,
+    pub timer: TimerSnapshot,
+    pub elapsed: f32,
+    pub calm_until: f32,
+    pub next_wave: usize,
+    pub timeline_start: f32,
+    pub active_waves: Vec<ActiveWaveSnapshot>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ActiveWaveSnapshot {
+    pub carrier: AgentServiceCode,
+    pub remaining: u32,
+    pub timer: TimerSnapshot,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ParcelSnapshot {
+    pub code: AgentServiceCode,
+    pub kind: ParcelKind,
+    /// Relative to its stack entry while stacked.
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub linvel: Vec3,
+    pub angvel: Vec3,
+    pub stacked: bool,
+    /// Where the parcel was thrown from, if it was.
+    pub thrown_from: Option<Vec3>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ShippingAreaSnapshot {
+    pub code: AgentServiceCode,
+    pub score: i32,
+    pub received_parcels: u64,
+    pub truck: Vec3,
+    /// The drive of a truck leaving with a delivery.
+    pub truck_drive: Option<TruckDriveSnapshot>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TruckDriveSnapshot {
+    pub origin: Vec3,
+    pub timer: TimerSnapshot,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ActivePowerUpSnapshot {
+    pub kind: PowerUpKind,
+    pub timer: TimerSnapshot,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PowerUpSnapshot {
+    pub kind: PowerUpKind,
+    pub translation: Vec3,
+    pub linvel: Vec3,
+    pub lifetime: TimerSnapshot,
+}
+
+impl Snapshot {
+    const FILE: &'static str = "snapshot.json";
+
+    /// Takes a snapshot of the running round and restores it right away, so the round plays on
+    /// exactly like any other round restored from the snapshot.
+    pub fn capture(world: &mut World) -> Self {
+        let snapshot = run_once(capture_round, (), world);
+        snapshot.restore(world);
+        snapshot
+    }
+
+    /// Puts the running round into the state of the snapshot.
+    ///
+    /// The parcels and the physics are rebuilt from scratch in a fixed order, Rapier only repeats
+    /// a simulation whose bodies are inserted in the same order.
+    pub fn restore(&self, world: &mut World) {
+        world.insert_resource(self.mode);
+        world.insert_resource(self.difficulty);
+        world.insert_resource(self.difficulty.curve());
+        if *world.resource::<Mutators>() != self.mutators {
+            world.insert_resource(self.mutators.clone());
+            run_once(apply_gravity, (), world);
+        }
+
+        run_once(rebuild_parcels, (), world);
+        let restored = run_once(restore_round, self.clone(), world);
+        rebuild_physics(world, restored);
+        run_once(update_spatial_tree, (), world);
+    }
+
+    /// Loads the snapshot saved last, `None` without one or if it's of another version.
+    pub fn load() -> Option<Self> {
+        let snapshot: Self = storage::load(Self::FILE)?;
+
+        if snapshot.version != SNAPSHOT_VERSION {
+            log::warn!(
+                "ignoring snapshot of version {}, expected {SNAPSHOT_VERSION}",
+                snapshot.version
+            );
+            return None;
+        }
+
+        Some(snapshot)
+    }
+
+    pub fn save(&self) {
+        storage::save(Self::FILE, self);
+    }
+}
+
+/// Swaps the [`RapierContext`] for an empty one and inserts the bodies again: the warehouse by
+/// position, the player, the `restored` parcels and power-ups in their order, then the pool.
+fn rebuild_physics(world: &mut World, restored: Vec<Entity>) {
+    // placeholder handles keep the bodies from being inserted before their turn
+    let bodies: Vec<Entity> = world
+        .query_filtered::<Entity, With<RigidBody>>()
+        .iter(world)
+        .collect();
+    for body in bodies {
+        world
+            .entity_mut(body)
+            .insert(RapierRigidBodyHandle(RigidBodyHandle::invalid()));
+    }
+    let colliders: Vec<Entity> = world
+        .query_filtered::<Entity, With<Collider>>()
+        .iter(world)
+        .collect();
+    for collider in colliders {
+        world
+            .entity_mut(collider)
+            .insert(RapierColliderHandle(ColliderSet::invalid_handle()));
+    }
+
+    let mut context = RapierContext::default();
+    context.integration_parameters = world.resource::<RapierContext>().integration_parameters;
+    world.insert_resource(context);
+    run_once(transform_propagate_system, (), world);
+
+    let mut warehouse: Vec<(Vec3, Entity)> = world
+        .query_filtered::<(Entity, &GlobalTransform, Option<&Parent>), (
+            Or<(With<RigidBody>, With<Collider>)>,
+            Without<Player>,
+            Without<ParcelKind>,
+            Without<PowerUp>,
+        )>()
+        .iter(world)
+        // colliders attached to a body or to another collider go in with it
+        .filter(|(entity, _, parent)| {
+            world.get::<RigidBody>(*entity).is_some()
+                || !parent.is_some_and(|parent| has_physics(world, parent.get()))
+        })
+        .map(|(entity, transform, _)| (transform.translation(), entity))
+        .collect();
+    warehouse.sort_by(|(a, _), (b, _)| by_position(*a, *b));
+
+    let player = world.query_filtered::<Entity, With<Player>>().single(world);
+    let pool: Vec<Entity> = world
+        .query_filtered::<Entity, With<Pooled>>()
+        .iter(world)
+        .collect();
+    let order = warehouse
+        .into_iter()
+        .map(|(_, entity)| entity)
+        .chain([player])
+        .chain(restored)
+        .chain(pool);
+
+    let mut init_bodies = IntoSystem::into_system(init_rigid_bodies);
+    let mut init_colliders = IntoSystem::into_system(init_colliders);
+    init_bodies.initialize(world);
+    init_colliders.initialize(world);
+    for entity in order {
+        world.entity_mut(entity).remove::<RapierRigidBodyHandle>();
+        reveal_colliders(world, entity);
+
+        init_bodies.run((), world);
+        init_bodies.apply_buffers(world);
+        init_colliders.run((), world);
+        init_colliders.apply_buffers(world);
+    }
+
+    // the handles taken off above aren't bodies that Rapier should remove
+    world.clear_trackers();
+}
+
+fn by_position(a: Vec3, b: Vec3) -> Ordering {
+    a.x.total_cmp(&b.x)
+        .then(a.y.total_cmp(&b.y))
+        .then(a.z.total_cmp(&b.z))
+}
+
+/// Whether `entity` or one of its ancestors is a body or a collider.
+fn has_physics(world: &World, entity: Entity) -> bool {
+    world.get::<RigidBody>(entity).is_some()
+        || world.get::<Collider>(entity).is_some()
+        || world
+            .get::<Parent>(entity)
+            .is_some_and(|parent| has_physics(world, parent.get()))
+}
+
+/// Takes the placeholder handles off the colliders of `entity` and its children, leaving out
+/// children with a body of their own.
+fn reveal_colliders(world: &mut World, entity: Entity) {
+    // children despawned without their parent knowing are still listed
+    let Some(mut entity_mut) = world.get_entity_mut(entity) else {
+        return;
+    };
+    entity_mut.remove::<RapierColliderHandle>();
+
+    let children = world
+        .get::<Children>(entity)
+        .map(|children| children.to_vec())
+        .unwrap_or_default();
+    for child in children {
+        if world.get::<RigidBody>(child).is_none() {
+            reveal_colliders(world, child);
+        }
+    }
+}
+
+/// Runs `system` right away on `world` and applies its commands.
+fn run_once<In, Out, Param>(
+    system: impl IntoSystem<In, Out, Param>,
+    input: In,
+    world: &mut World,
+) -> Out {
+    let mut system = IntoSystem::into_system(system);
+    system.initialize(world);
+    let out = system.run(input, world);
+    system.apply_buffers(world);
+    out
+}
+
+fn quick_save(world: &mut World) {
+    if world.resource::<Input<KeyCode>>().just_pressed(KeyCode::F5) {
+        Snapshot::capture(world).save();
+    }
+}
+
+fn quick_load(world: &mut World) {
+    if !world.resource::<Input<KeyCode>>().just_pressed(KeyCode::F9) {
+        return;
+    }
+
+    if let Some(snapshot) = Snapshot::load() {
+        snapshot.restore(world);
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn capture_round(
+    (mode, difficulty, mutators): (Res<GameMode>, Res<Difficulty>, Res<Mutators>),
+    rng: Res<GameRng>,
+    score: Res<Score>,
+    time_remaining: Res<TimeRemaining>,
+    stats: Res<RoundStats>,
+    spawner: Res<ParcelSpawner>,
+    player: Query<(&Transform, &PlayerBody, &Stamina, &Dash), With<Player>>,
+    stack: Query<(&ParcelStack, &StackBalance)>,
+    entries: Query<&ParcelStackEntry>,
+    parcels: Query<(
+        &Transform,
+        &Velocity,
+        &AgentServiceCode,
+        &ParcelKind,
+        Option<&Thrown>,
+    )>,
+    free_parcels: Query<Entity, (With<Parcel>, Without<Picked>, Without<Despawn>)>,
+    shipping_areas: Query<(&AgentServiceCode, &ShippingArea)>,
+    trucks: Query<(&Transform, Option<&MoveTruck>), With<Truck>>,
+    active_power_ups: Res<ActivePowerUps>,
+    power_ups: Query<(&PowerUpKind, &PowerUp, &Transform, &Velocity)>,
+) -> Snapshot {
+    let (player, body, stamina, dash) = player.single();
+    let (stack, balance) = stack.single();
+    let stacked = stack
+        .parcels_entries
+        .iter()
+        .filter_map(|entry| entries.get(*entry).ok()?.parcel)
+        .map(|parcel| (parcel, true));
+    let free = free_parcels.iter().map(|parcel| (parcel, false));
+
+    let mut parcels: Vec<ParcelSnapshot> = stacked
+        .chain(free)
+        .filter_map(|(parcel, stacked)| {
+            let (transform, velocity, code, kind, thrown) = parcels.get(parcel).ok()?;
+
+            Some(ParcelSnapshot {
+                code: *code,
+                kind: *kind,
+                translation: transform.translation,
+                rotation: transform.rotation,
+                linvel: velocity.linvel,
+                angvel: velocity.angvel,
+                stacked,
+                thrown_from: thrown.map(|thrown| thrown.from),
+            })
+        })
+        .collect();
+    // the order of the rest is up to the ECS, it's the order the parcels are restored in
+    let stacked = parcels.iter().filter(|parcel| parcel.stacked).count();
+    parcels[stacked..].sort_by(|a, b| by_position(a.translation, b.translation));
+
+    let mut shipping_areas: Vec<ShippingAreaSnapshot> = shipping_areas
+        .iter()
+        .map(|(code, area)| {
+            let (truck, drive) = trucks.get(area.truck).unwrap();
+
+            ShippingAreaSnapshot {
+                code: *code,
+                score: area.score,
+                received_parcels: area.received_parcels,
+                truck: truck.translation,
+                truck_drive: drive.map(|drive| TruckDriveSnapshot {
+                    origin: drive.origin,
+                    timer: TimerSnapshot::new(&drive.timer),
+                }),
+            }
+        })
+        .collect();
+    shipping_areas.sort_by_key(|area| area.code as u8);
+
+    let mut power_ups: Vec<PowerUpSnapshot> = power_ups
+        .iter()
+        .map(|(kind, power_up, transform, velocity)| PowerUpSnapshot {
+            kind: *kind,
+            translation: transform.translation,
+            linvel: velocity.linvel,
+            lifetime: TimerSnapshot::new(&power_up.lifetime),
+        })
+        .collect();
+    power_ups.sort_by(|a, b| by_position(a.translation, b.translation));
+
+    Snapshot {
+        version: SNAPSHOT_VERSION,
+        mode: *mode,
+        difficulty: *difficulty,
+        mutators: mutators.clone(),
+        rng: rng.clone(),
+        score: score.score,
+        bonus: score.bonus,
+        time_remaining: TimerSnapshot::new(&time_remaining.timer),
+        stats: stats.clone(),
+        spawner: SpawnerSnapshot {
+            count: spawner.count,
+            timer: TimerSnapshot::new(&spawner.timer),
+            elapsed: spawner.elapsed,
+            calm_until: spawner.calm_until,
+            next_wave: spawner.next_wave,
+            timeline_start: spawner.timeline_start,
+            active_waves: spawner
+                .active_waves
+                .iter()
+                .map(|wave| ActiveWaveSnapshot {
+                    carrier: wave.carrier,
+                    remaining: wave.remaining,
+                    timer: TimerSnapshot::new(&wave.timer),
+                })
+                .collect(),
+        },
+        player: player.translation,
+        player_body: body.clone(),
+        stamina: stamina.clone(),
+        dash: dash.clone(),
+        stack_capacity: stack.capacity,
+        stack_balance: balance.clone(),
+        parcels,
+        shipping_areas,
+        active_power_ups: active_power_ups
+            .0
+            .iter()
+            .map(|active| ActivePowerUpSnapshot {
+                kind: active.kind,
+                timer: TimerSnapshot::new(&active.timer),
+            })
+            .collect(),
+        power_ups,
+    }
+}
+
+/// Returns the restored parcels and power-ups in the order of the snapshot.
+#[allow(clippy::too_many_arguments)]
+fn restore_round(
+    In(snapshot): In<Snapshot>,
+    mut parcels: ParcelCommands,
+    mut rng: ResMut<GameRng>,
+    mut score: ResMut<Score>,
+    mut time_remaining: ResMut<TimeRemaining>,
+    mut stats: ResMut<RoundStats>,
+    mut spawner: ResMut<ParcelSpawner>,
+    mut closest_parcel: ResMut<ClosestParcel>,
+    (mut add_events, mut pop_events, mut collision_events): (
+        ResMut<Events<AddParcelToStack>>,
+        ResMut<Events<PopParcelFromStack>>,
+        ResMut<Events<CollisionEvent>>,
+    ),
+    mut player: Query<
+        (
+            Entity,
+            &mut Transform,
+            &mut PlayerBody,
+            &mut Stamina,
+            &mut Dash,
+            &mut KinematicCharacterController,
+        ),
+        With<Player>,
+    >,
+    mut stack: Query<(Entity, &mut ParcelStack, &mut StackBalance)>,
+    mut shipping_areas: Query<(&AgentServiceCode, &mut ShippingArea)>,
+    mut trucks: Query<&mut Transform, (With<Truck>, Without<Player>)>,
+    (mut active_power_ups, power_up_assets, power_ups): (
+        ResMut<ActivePowerUps>,
+        Res<PowerUpAssets>,
+        Query<Entity, With<PowerUp>>,
+    ),
+) -> Vec<Entity> {
+    *rng = snapshot.rng;
+    score.score = snapshot.score;
+    score.bonus = snapshot.bonus;
+    snapshot.time_remaining.restore(&mut time_remaining.timer);
+    *stats = snapshot.stats;
+
+    // events of the round that is replaced would act on parcels that are gone
+    closest_parcel.0 = None;
+    add_events.clear();
+    pop_events.clear();
+    collision_events.clear();
+
+    let (player, mut transform, mut body, mut stamina, mut dash, mut controller) =
+        player.single_mut();
+    transform.translation = snapshot.player;
+    *body = snapshot.player_body;
+    *stamina = snapshot.stamina;
+    *dash = snapshot.dash;
+    controller.translation = None;
+    parcels
+        .commands()
+        .entity(player)
+        .remove::<KinematicCharacterControllerOutput>()
+        .insert(Interpolated::default());
+
+    // the parcels were rebuilt, the snapshot's ones are spawned anew
+    let (stack_entity, mut stack, mut balance) = stack.single_mut();
+    let mut restored = Vec::with_capacity(snapshot.parcels.len() + snapshot.power_ups.len());
+    for entry in stack.parcels_entries.drain(..) {
+        parcels.commands().entity(entry).despawn();
+    }
+    stack.capacity = snapshot.stack_capacity;
+    *balance = snapshot.stack_balance;
+
+    for parcel in &snapshot.parcels {
+        let entity = parcels.spawn(
+            &mut spawner,
+            parcel.translation,
+            parcel.linvel,
+            parcel.code,
+            parcel.kind,
+        );
+
+        parcels.commands().entity(entity).insert((
+            Transform::from_translation(parcel.translation).with_rotation(parcel.rotation),
+            Velocity {
+                linvel: parcel.linvel,
+                angvel: parcel.angvel,
+            },
+            Interpolated::default(),
+        ));
+
+        restored.push(entity);
+
+        if parcel.stacked {
+            let index = stack.parcels_entries.len();
+            let entry = stack_parcel(parcels.commands(), stack_entity, index, entity);
+            stack.parcels_entries.push(entry);
+        } else if let Some(from) = parcel.thrown_from {
+            parcels.commands().entity(entity).insert(Thrown { from });
+        }
+    }
+
+    let spawner = &mut *spawner;
+    spawner.count = snapshot.spawner.count;
+    snapshot.spawner.timer.restore(&mut spawner.timer);
+    spawner.elapsed = snapshot.spawner.elapsed;
+    spawner.calm_until = snapshot.spawner.calm_until;
+    spawner.next_wave = snapshot.spawner.next_wave;
+    spawner.timeline_start = snapshot.spawner.timeline_start;
+    spawner.active_waves = snapshot
+        .spawner
+        .active_waves
+        .iter()
+        .map(|wave| {
+            let mut timer = Timer::new(wave.timer.duration, TimerMode::Repeating);
+            wave.timer.restore(&mut timer);
+
+            ActiveWave {
+                carrier: wave.carrier,
+                remaining: wave.remaining,
+                timer,
+            }
+        })
+        .collect();
+
+    for (code, mut area) in &mut shipping_areas {
+        let Some(saved) = snapshot
+            .shipping_areas
+            .iter()
+            .find(|saved| saved.code == *code)
+        else {
+            continue;
+        };
+
+        area.score = saved.score;
+        area.received_parcels = saved.received_parcels;
+
+        if let Ok(mut transform) = trucks.get_mut(area.truck) {
+            transform.translation = saved.truck;
+        }
+
+        let mut truck = parcels.commands().entity(area.truck);
+        truck.insert(Interpolated::default());
+        match &saved.truck_drive {
+            Some(drive) => {
+                let mut timer = Timer::new(drive.timer.duration, TimerMode::Once);
+                drive.timer.restore(&mut timer);
+                truck.insert(MoveTruck {
+                    origin: drive.origin,
+                    timer,
+                });
+            }
+            None => {
+                truck.remove::<MoveTruck>();
+            }
+        }
+    }
+
+    active_power_ups.0 = snapshot
+        .active_power_ups
+        .iter()
+        .map(|active| {
+            let mut timer = Timer::new(active.timer.duration, TimerMode::Once);
+            active.timer.restore(&mut timer);
+
+            ActivePowerUp {
+                kind: active.kind,
+                timer,
+            }
+        })
+        .collect();
+
+    for power_up in &power_ups {
+        parcels.commands().entity(power_up).despawn_recursive();
+    }
+    for saved in &snapshot.power_ups {
+        let commands = parcels.commands();
+        let power_up = spawn_power_up(
+            commands,
+            &power_up_assets,
+            saved.kind,
+            saved.translation,
+            spawner.parent,
+        );
+
+        let mut lifetime = Timer::new(saved.lifetime.duration, TimerMode::Once);
+        saved.lifetime.restore(&mut lifetime);
+        commands
+            .entity(power_up)
+            .insert((PowerUp { lifetime }, Velocity::linear(saved.linvel)));
+        restored.push(power_up);
+    }
+
+    restored
+}
@@ -1,6 +1,8 @@
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub enum GameState {
     Loading,
+    Menu,
+    Settings,
     Ready,
     GameOver,
 }
@@ -0,0 +1,76 @@
+//! Small JSON files kept in the player's data directory, e.g. high scores, and text exports.
+//! Nothing is kept between sessions on the web.
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::prelude::*;
+
+/// Reads `name` from the data directory, `None` if it was never saved or can't be read.
+pub fn load<T: DeserializeOwned>(name: &str) -> Option<T> {
+    let path = data_dir()?.join(name);
+    let contents = std::fs::read_to_string(&path).ok()?;
+
+    serde_json::from_str(&contents)
+        .map_err(|err| log::warn!("ignoring unreadable {}: {err}", path.display()))
+        .ok()
+}
+
+/// Writes `value` as `name` to the data directory, failures are only logged.
+pub fn save<T: Serialize>(name: &str, value: &T) {
+    let Some(dir) = data_dir() else {
+        return;
+    };
+
+    let result = std::fs::create_dir_all(&dir).and_then(|_| {
+        let contents = serde_json::to_string_pretty(value)?;
+        std::fs::write(dir.join(name), contents)
+    });
+
+    if let Err(err) = result {
+        log::warn!("could not save {name} to {}: {err}", dir.display());
+    }
+}
+
+/// Writes plain `contents` as `name` to the data directory and returns where it went.
+pub fn export(name: &str, contents: &str) -> Option<std::path::PathBuf> {
+    let path = data_dir()?.join(name);
+
+    std::fs::create_dir_all(path.parent()?)
+        .and_then(|_| std::fs::write(&path, contents))
+        .map_err(|err| log::warn!("could not export {}: {err}", path.display()))
+        .ok()?;
+
+    Some(path)
+}
+
+/// `BLACK_FRIDAY_DATA_DIR` if set, the platform's data directory otherwise.
+#[cfg(not(target_arch = "wasm32"))]
+fn data_dir() -> Option<std::path::PathBuf> {
+    use std::{env, path::PathBuf};
+
+    const APP_DIR: &str = "black_friday";
+
+    if let Some(dir) = env::var_os("BLACK_FRIDAY_DATA_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+
+    if cfg!(target_os = "windows") {
+        env::var_os("APPDATA").map(|dir| PathBuf::from(dir).join(APP_DIR))
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|dir| {
+            PathBuf::from(dir)
+                .join("Library/Application Support")
+                .join(APP_DIR)
+        })
+    } else {
+        env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|dir| PathBuf::from(dir).join(".local/share")))
+            .map(|dir| dir.join(APP_DIR))
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn data_dir() -> Option<std::path::PathBuf> {
+    None
+}
@@ -0,0 +1,175 @@
+//! Every gameplay event of a round as one JSON line, written to the data directory once the
+//! round is over, e.g. to balance the spawn rates. Only built with the `telemetry` feature.
+
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU32, Ordering},
+    time::SystemTime,
+};
+
+use serde::Serialize;
+
+use crate::{
+    on_fixed_update, prelude::*, storage, AddParcelToStack, AgentServiceCode, FixedAppExt,
+    FixedStage, Parcel, ParcelDelivered, ParcelKind, ParcelLost, ParcelThrown, RoundStats, Score,
+};
+
+pub struct TelemetryPlugin;
+
+impl Plugin for TelemetryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Telemetry>();
+        app.add_system_set(SystemSet::on_enter(GameState::Ready).with_system(reset_telemetry))
+            .add_fixed_system_set_to_stage(
+                FixedStage::PostUpdate,
+                on_fixed_update(GameState::Ready).with_system(log_events),
+            )
+            .add_system_set(SystemSet::on_enter(GameState::GameOver).with_system(write_telemetry));
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum TelemetryEvent {
+    ParcelSpawned {
+        carrier: AgentServiceCode,
+        kind: ParcelKind,
+        position: Vec3,
+    },
+    ParcelPickedUp {
+        carrier: AgentServiceCode,
+    },
+    ParcelThrown {
+        carrier: AgentServiceCode,
+        velocity: Vec3,
+    },
+    ParcelDelivered {
+        carrier: AgentServiceCode,
+        area: AgentServiceCode,
+        correct: bool,
+        /// Seconds since the parcel was spawned.
+        time_alive: f32,
+    },
+    ParcelLost {
+        carrier: AgentServiceCode,
+    },
+    ScoreChanged {
+        score: i32,
+        change: i32,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TelemetryRecord {
+    /// Seconds into the round.
+    pub time: f32,
+    #[serde(flatten)]
+    pub event: TelemetryEvent,
+}
+
+/// Events of the ongoing round.
+#[derive(Resource, Default)]
+pub struct Telemetry {
+    pub records: Vec<TelemetryRecord>,
+    spawned_at: HashMap<Entity, f32>,
+    score: i32,
+}
+
+impl Telemetry {
+    /// The records as JSON lines.
+    pub fn to_json_lines(&self) -> String {
+        self.records
+            .iter()
+            .map(|record| serde_json::to_string(record).unwrap() + "\n")
+            .collect()
+    }
+}
+
+fn reset_telemetry(mut telemetry: ResMut<Telemetry>) {
+    *telemetry = Telemetry::default();
+}
+
+fn log_events(
+    mut telemetry: ResMut<Telemetry>,
+    stats: Res<RoundStats>,
+    score: Res<Score>,
+    mut picked_up: EventReader<AddParcelToStack>,
+    mut thrown: EventReader<ParcelThrown>,
+    mut delivered: EventReader<ParcelDelivered>,
+    mut lost: EventReader<ParcelLost>,
+    spawned: Query<(Entity, &Transform, &AgentServiceCode, &ParcelKind), Added<Parcel>>,
+    parcels: Query<&AgentServiceCode>,
+) {
+    let time = stats.elapsed;
+    let mut events = vec![];
+
+    for (entity, transform, code, kind) in &spawned {
+        telemetry.spawned_at.insert(entity, time);
+        events.push(TelemetryEvent::ParcelSpawned {
+            carrier: *code,
+            kind: *kind,
+            position: transform.translation,
+        });
+    }
+
+    for event in picked_up.iter() {
+        if let Ok(code) = parcels.get(event.parcel) {
+            events.push(TelemetryEvent::ParcelPickedUp { carrier: *code });
+        }
+    }
+
+    for event in thrown.iter() {
+        if let Ok(code) = parcels.get(event.parcel) {
+            events.push(TelemetryEvent::ParcelThrown {
+                carrier: *code,
+                velocity: event.velocity,
+            });
+        }
+    }
+
+    for event in delivered.iter() {
+        let spawned_at = telemetry.spawned_at.remove(&event.parcel).unwrap_or(time);
+        events.push(TelemetryEvent::ParcelDelivered {
+            carrier: event.code,
+            area: event.area,
+            correct: event.is_correct(),
+            time_alive: time - spawned_at,
+        });
+    }
+
+    for event in lost.iter() {
+        events.push(TelemetryEvent::ParcelLost {
+            carrier: event.code,
+        });
+    }
+
+    if score.score != telemetry.score {
+        events.push(TelemetryEvent::ScoreChanged {
+            score: score.score,
+            change: score.score - telemetry.score,
+        });
+        telemetry.score = score.score;
+    }
+
+    telemetry.records.extend(
+        events
+            .into_iter()
+            .map(|event| TelemetryRecord { time, event }),
+    );
+}
+
+fn write_telemetry(telemetry: Res<Telemetry>) {
+    // tells apart the rounds of one session, e.g. simulated side by side
+    static ROUNDS: AtomicU32 = AtomicU32::new(0);
+
+    let now = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis();
+    let round = ROUNDS.fetch_add(1, Ordering::Relaxed);
+
+    let name = format!("telemetry/round-{now}-{round}.jsonl");
+    if let Some(path) = storage::export(&name, &telemetry.to_json_lines()) {
+        log::info!("telemetry written to {}", path.display());
+    }
+}
@@ -0,0 +1,280 @@
+use crate::{
+    parcel_size, player_input, prelude::*, AgentServiceCode, Mutators, ParcelKind, ParcelStack,
+    ParcelStackEntry, Player, PlayerActions, ShippingArea, ThrowCharge, WritePlayerActions,
+};
+
+/// Previews where the top parcel lands, shows the throw charge and optionally assists aiming,
+/// only added to the windowed game. Aim assist is toggled with Q.
+pub struct ThrowAimPlugin;
+
+impl Plugin for ThrowAimPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(AimAssist(false));
+        app.add_system_set(
+            SystemSet::on_update(GameState::Ready)
+                .with_system(toggle_aim_assist)
+                .with_system(
+                    aim_assist
+                        .label(WritePlayerActions)
+                        .after(player_input)
+                        .after(toggle_aim_assist),
+                )
+                .with_system(preview_throw.after(WritePlayerActions))
+                .with_system(draw_throw_charge.after(WritePlayerActions)),
+        );
+    }
+}
+
+/// Snaps aiming towards the shipping area of the parcel about to be thrown when enabled.
+#[derive(Resource)]
+pub struct AimAssist(pub bool);
+
+/// Height of the center of a parcel of `kind` when it rests on the floor.
+pub fn landing_height(kind: ParcelKind, mutators: &Mutators) -> f32 {
+    config::FLOOR_HEIGHT + parcel_size(kind, mutators) / 2.0
+}
+
+/// Velocity given to a parcel thrown from `from` towards `aim`, both the reach and the arc
+/// height are scaled by `power`.
+pub fn throw_velocity(from: Vec3, aim: Vec3, power: f32) -> Vec3 {
+    let linvel = ((aim - from) * config::PLAYER_THROW_FACTOR)
+        .clamp_length_max(config::PLAYER_MAX_THROW_MAQ)
+        .clamp_length_min(config::PLAYER_MIN_THROW_MAQ);
+
+    (linvel + Vec3::Y * config::PLAYER_THROW_LIFT) * power
+}
+
+/// Position of a parcel thrown from `from` with `linvel` after `t` seconds of flight.
+pub fn throw_position(from: Vec3, linvel: Vec3, gravity: Vec3, t: f32) -> Vec3 {
+    from + linvel * t + 0.5 * gravity * t * t
+}
+
+/// Seconds until a parcel thrown from `from` with `linvel` comes down to its `landing_height`,
+/// `None` if it never does.
+pub fn throw_flight_time(
+    from: Vec3,
+    linvel: Vec3,
+    gravity: Vec3,
+    landing_height: f32,
+) -> Option<f32> {
+    let (a, b, c) = (0.5 * gravity.y, linvel.y, from.y - landing_height);
+    let discriminant = b * b - 4.0 * a * c;
+
+    if a >= 0.0 || discriminant < 0.0 {
+        return None;
+    }
+
+    Some((-b - discriminant.sqrt()) / (2.0 * a))
+}
+
+/// Point on the ground to aim at for a parcel thrown from `from` with `power` to land as close
+/// to `target` as the throw clamps allow.
+pub fn aim_for_target(
+    from: Vec3,
+    target: Vec3,
+    gravity: Vec3,
+    power: f32,
+    landing_height: f32,
+) -> Vec3 {
+    let origin = Vec3::new(from.x, landing_height, from.z);
+    let direction = (target - origin).reject_from(Vec3::Y).normalize_or_zero();
+    let distance = (target - origin).reject_from(Vec3::Y).length();
+
+    let landing_distance = |aim_distance: f32| {
+        let linvel = throw_velocity(from, origin + direction * aim_distance, power);
+        throw_flight_time(from, linvel, gravity, landing_height)
+            .map(|t| (throw_position(from, linvel, gravity, t) - origin).reject_from(Vec3::Y))
+            .map_or(0.0, |offset| offset.dot(direction))
+    };
+
+    // landing distance grows with the aimed distance until the throw is clamped
+    let (mut low, mut high) = (0.0, config::PLAYER_MAX_THROW_MAQ * 2.0);
+    for _ in 0..16 {
+        let mid = (low + high) / 2.0;
+        if landing_distance(mid) < distance {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+
+    origin + direction * (low + high) / 2.0
+}
+
+fn toggle_aim_assist(keyboard_input: Res<Input<KeyCode>>, mut assist: ResMut<AimAssist>) {
+    if keyboard_input.just_pressed(KeyCode::Q) {
+        assist.0 = !assist.0;
+    }
+}
+
+fn aim_assist(
+    assist: Res<AimAssist>,
+    rapier_config: Res<RapierConfiguration>,
+    mutators: Res<Mutators>,
+    mut actions: ResMut<PlayerActions>,
+    parcel_stack: Query<&ParcelStack>,
+    stack_entries: Query<(&ParcelStackEntry, &GlobalTransform)>,
+    parcels: Query<(&AgentServiceCode, &ParcelKind)>,
+    shipping_areas: Query<(&Transform, &AgentServiceCode), With<ShippingArea>>,
+) {
+    let Some(aim) = actions.aim else {
+        return;
+    };
+
+    if !assist.0 {
+        return;
+    }
+
+    let Some((from, code, kind)) =
+        stack_parcel(actions.slot, &parcel_stack, &stack_entries, &parcels)
+    else {
+        return;
+    };
+    let landing_height = landing_height(kind, &mutators);
+
+    let Some((area, _)) = shipping_areas.iter().find(|(_, c)| **c == code) else {
+        return;
+    };
+
+    // closest point of the area to the thrower, kept a few parcels away from its edges
+    let half_size = config::GROUND_SIZE / 2.0 - config::PARCEL_SIZE * 2.0;
+    let offset = (from - area.translation).clamp(Vec3::splat(-half_size), Vec3::splat(half_size));
+    let target = Vec3::new(
+        area.translation.x + offset.x,
+        landing_height,
+        area.translation.z + offset.z,
+    );
+
+    let aimed = (aim - from).reject_from(Vec3::Y);
+    let wanted = (target - from).reject_from(Vec3::Y);
+    if aimed.angle_between(wanted) <= config::PLAYER_AIM_ASSIST_ANGLE.to_radians() {
+        actions.aim = Some(aim_for_target(
+            from,
+            target,
+            rapier_config.gravity,
+            actions.power,
+            landing_height,
+        ));
+    }
+}
+
+fn preview_throw(
+    actions: Res<PlayerActions>,
+    rapier_config: Res<RapierConfiguration>,
+    mutators: Res<Mutators>,
+    mut lines: ResMut<DebugLines>,
+    parcel_stack: Query<&ParcelStack>,
+    stack_entries: Query<(&ParcelStackEntry, &GlobalTransform)>,
+    parcels: Query<(&AgentServiceCode, &ParcelKind)>,
+    shipping_areas: Query<(&Transform, &AgentServiceCode), With<ShippingArea>>,
+) {
+    const SEGMENTS: usize = 24;
+
+    let Some(aim) = actions.aim else {
+        return;
+    };
+
+    let Some((from, code, kind)) =
+        stack_parcel(actions.slot, &parcel_stack, &stack_entries, &parcels)
+    else {
+        return;
+    };
+
+    let gravity = rapier_config.gravity;
+    let linvel = throw_velocity(from, aim, actions.power);
+    let landing_height = landing_height(kind, &mutators);
+    let Some(flight_time) = throw_flight_time(from, linvel, gravity, landing_height) else {
+        return;
+    };
+
+    let landing = throw_position(from, linvel, gravity, flight_time);
+    let landing_area = shipping_areas.iter().find(|(transform, _)| {
+        let offset = (landing - transform.translation).abs();
+        offset.x <= config::GROUND_SIZE / 2.0 && offset.z <= config::GROUND_SIZE / 2.0
+    });
+
+    let color = match landing_area {
+        Some((_, area_code)) if *area_code == code => Color::GREEN,
+        Some(_) => Color::RED,
+        None => Color::WHITE,
+    };
+
+    let mut previous = from;
+    for i in 1..=SEGMENTS {
+        let t = flight_time * i as f32 / SEGMENTS as f32;
+        let point = throw_position(from, linvel, gravity, t);
+        lines.line_colored(previous, point, 0.0, color);
+        previous = point;
+    }
+
+    lines.circle(landing, config::PARCEL_SIZE / 2.0, 0.0, color);
+
+    if actions.slot.is_some() {
+        lines.circle(from, config::PARCEL_SIZE, 0.0, code.color());
+    }
+
+    if let Some((transform, area_code)) = landing_area {
+        lines.square(
+            transform.translation + Vec3::Y * 0.05,
+            config::GROUND_SIZE,
+            0.0,
+            area_code.color(),
+        );
+    }
+}
+
+fn draw_throw_charge(
+    charge: Res<ThrowCharge>,
+    mut lines: ResMut<DebugLines>,
+    player: Query<&Transform, With<Player>>,
+) {
+    const WIDTH: f32 = 1.5;
+    const HEIGHT: f32 = 0.15;
+    const ROWS: usize = 4;
+
+    if charge.seconds <= 0.0 || charge.fumbled {
+        return;
+    }
+
+    let origin = player.single().translation + Vec3::new(-WIDTH / 2.0, 4.5, 0.0);
+    let fraction = charge.fraction();
+    let color = if charge.seconds >= config::PLAYER_THROW_CHARGE_TIME {
+        // fully charged, holding on now risks a fumble
+        Color::RED
+    } else {
+        Color::rgb(fraction, 1.0, 0.0)
+    };
+
+    for row in 0..=ROWS {
+        let start = origin + Vec3::Y * HEIGHT * row as f32 / ROWS as f32;
+        lines.line_colored(start, start + Vec3::X * WIDTH * fraction, 0.0, color);
+    }
+
+    let outline = [
+        origin,
+        origin + Vec3::X * WIDTH,
+        origin + Vec3::new(WIDTH, HEIGHT, 0.0),
+        origin + Vec3::Y * HEIGHT,
+    ];
+    for (i, start) in outline.iter().enumerate() {
+        lines.line_colored(*start, outline[(i + 1) % outline.len()], 0.0, Color::WHITE);
+    }
+}
+
+/// Position, carrier and kind of the parcel in the stack `slot`, or on top of the stack if
+/// `None` or the slot is empty.
+fn stack_parcel(
+    slot: Option<usize>,
+    parcel_stack: &Query<&ParcelStack>,
+    stack_entries: &Query<(&ParcelStackEntry, &GlobalTransform)>,
+    parcels: &Query<(&AgentServiceCode, &ParcelKind)>,
+) -> Option<(Vec3, AgentServiceCode, ParcelKind)> {
+    let entries = &parcel_stack.single().parcels_entries;
+    let entry = *slot
+        .and_then(|slot| entries.get(slot))
+        .or_else(|| entries.last())?;
+    let (entry, transform) = stack_entries.get(entry).ok()?;
+    let (code, kind) = parcels.get(entry.parcel?).ok()?;
+
+    Some((transform.translation(), *code, *kind))
+}
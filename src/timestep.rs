@@ -0,0 +1,271 @@
+//! Gameplay and physics advance in steps of [`config::FIXED_TIMESTEP`], however long frames take,
+//! so a round plays out the same at 30 and at 240 FPS.
+
+use std::time::Duration;
+
+use bevy::{
+    ecs::{event::Event, schedule::ShouldRun},
+    transform::transform_propagate_system,
+};
+
+use crate::prelude::*;
+
+/// Runs [`FixedStage::PreUpdate`], [`FixedStage::Update`], the Rapier stages and
+/// [`FixedStage::PostUpdate`] once for every [`config::FIXED_TIMESTEP`] that passed, right after
+/// [`CoreStage::Update`].
+///
+/// Systems in there see the step as [`Time::delta`] and add their sets through [`FixedAppExt`].
+/// State transitions still happen in [`CoreStage::Update`], sets in here only run while in a
+/// state with [`on_fixed_update`].
+pub struct FixedTimestepPlugin;
+
+impl Plugin for FixedTimestepPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(
+            RapierPhysicsPlugin::<NoUserData>::default().with_default_system_setup(false),
+        );
+        app.init_resource::<FixedClock>();
+
+        let physics_stage = |stage: PhysicsStages| {
+            SystemStage::parallel()
+                .with_system_set(RapierPhysicsPlugin::<NoUserData>::get_systems(stage))
+        };
+
+        app.add_stage_after(
+            CoreStage::Update,
+            FixedUpdateStage,
+            Schedule::default()
+                .with_run_criteria(run_fixed_steps)
+                .with_stage(FixedStage::PreUpdate, SystemStage::parallel())
+                .with_stage(FixedStage::Update, SystemStage::parallel())
+                .with_stage(
+                    PhysicsStages::SyncBackend,
+                    physics_stage(PhysicsStages::SyncBackend),
+                )
+                .with_stage(
+                    PhysicsStages::StepSimulation,
+                    physics_stage(PhysicsStages::StepSimulation),
+                )
+                .with_stage(
+                    PhysicsStages::Writeback,
+                    physics_stage(PhysicsStages::Writeback),
+                )
+                .with_stage(
+                    FixedStage::PostUpdate,
+                    // the next step sees where the physics step moved everything
+                    SystemStage::parallel().with_system(transform_propagate_system),
+                ),
+        );
+
+        // catches removals made outside of the steps, like Rapier's own setup does
+        app.add_stage_before(
+            CoreStage::Last,
+            PhysicsStages::DetectDespawn,
+            physics_stage(PhysicsStages::DetectDespawn),
+        );
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, StageLabel)]
+pub struct FixedUpdateStage;
+
+/// Stages of every fixed step, the Rapier stages run in between.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, StageLabel)]
+pub enum FixedStage {
+    /// Before the gameplay, e.g. replays write the [`PlayerActions`](crate::PlayerActions) of
+    /// the step.
+    PreUpdate,
+    /// Gameplay, before the physics step.
+    Update,
+    /// After the physics step wrote back the new positions.
+    PostUpdate,
+}
+
+pub trait FixedAppExt {
+    /// Adds `system_set` to [`FixedStage::Update`].
+    fn add_fixed_system_set(&mut self, system_set: SystemSet) -> &mut Self;
+
+    fn add_fixed_system_set_to_stage(
+        &mut self,
+        stage: FixedStage,
+        system_set: SystemSet,
+    ) -> &mut Self;
+
+    /// [`App::add_event`] for events sent and read in the fixed steps, they are kept for two
+    /// steps instead of two frames.
+    fn add_fixed_event<T: Event>(&mut self) -> &mut Self;
+}
+
+impl FixedAppExt for App {
+    fn add_fixed_system_set(&mut self, system_set: SystemSet) -> &mut Self {
+        self.add_fixed_system_set_to_stage(FixedStage::Update, system_set)
+    }
+
+    fn add_fixed_system_set_to_stage(
+        &mut self,
+        stage: FixedStage,
+        system_set: SystemSet,
+    ) -> &mut Self {
+        self.schedule
+            .stage(FixedUpdateStage, |schedule: &mut Schedule| {
+                schedule.add_system_set_to_stage(stage, system_set)
+            });
+        self
+    }
+
+    fn add_fixed_event<T: Event>(&mut self) -> &mut Self {
+        if !self.world.contains_resource::<Events<T>>() {
+            self.init_resource::<Events<T>>()
+                .add_fixed_system_set_to_stage(
+                    FixedStage::PostUpdate,
+                    SystemSet::new().with_system(Events::<T>::update_system),
+                );
+        }
+        self
+    }
+}
+
+/// [`SystemSet::on_update`] of sets in the fixed steps, where no state driver runs to end the
+/// loop of state run criteria.
+pub fn on_fixed_update(state: GameState) -> SystemSet {
+    SystemSet::new().with_run_criteria(move |current: Res<State<GameState>>| {
+        if *current.current() == state {
+            ShouldRun::Yes
+        } else {
+            ShouldRun::No
+        }
+    })
+}
+
+/// Clock of the fixed steps, swapped in for [`Time`] while they run.
+#[derive(Resource)]
+pub struct FixedClock {
+    time: Time,
+    /// Time of the frame, kept aside during the steps.
+    real: Option<Time>,
+    /// Frame time not yet simulated.
+    accumulator: Duration,
+}
+
+impl Default for FixedClock {
+    fn default() -> Self {
+        let mut time = Time::default();
+        // the first step gets a full delta
+        time.update_with_instant(time.startup());
+
+        Self {
+            time,
+            real: None,
+            accumulator: Duration::ZERO,
+        }
+    }
+}
+
+impl FixedClock {
+    /// How far the frame got into the next step, from `0.0` to `1.0`.
+    pub fn overstep(&self) -> f32 {
+        self.accumulator.as_secs_f32() / config::FIXED_TIMESTEP
+    }
+}
+
+fn run_fixed_steps(
+    mut clock: ResMut<FixedClock>,
+    mut time: ResMut<Time>,
+    state: Res<State<GameState>>,
+) -> ShouldRun {
+    // drops the frames that can't be caught up on instead of slowing down further
+    const MAX_ACCUMULATED: Duration = Duration::from_millis(250);
+
+    let step = Duration::from_secs_f32(config::FIXED_TIMESTEP);
+
+    let stepping = clock.real.is_some();
+    if !stepping {
+        clock.accumulator = (clock.accumulator + time.delta()).min(MAX_ACCUMULATED);
+        clock.real = Some(time.clone());
+    }
+
+    // a step ended the round, the rest waits until the transition in the next frame's update
+    let round_changed = stepping && state.is_changed();
+
+    if clock.accumulator >= step && !round_changed {
+        clock.accumulator -= step;
+        let now = clock
+            .time
+            .last_update()
+            .unwrap_or_else(|| clock.time.startup())
+            + step;
+        clock.time.update_with_instant(now);
+        *time = clock.time.clone();
+
+        ShouldRun::YesAndCheckAgain
+    } else {
+        *time = clock.real.take().unwrap();
+
+        ShouldRun::No
+    }
+}
+
+/// Renders entities moved by the fixed steps in between their last two positions, only added
+/// to the windowed game.
+pub struct RenderInterpolationPlugin;
+
+impl Plugin for RenderInterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system_to_stage(CoreStage::First, restore_simulated_transforms)
+            .add_fixed_system_set_to_stage(
+                FixedStage::PostUpdate,
+                SystemSet::new().with_system(record_simulated_transforms),
+            )
+            .add_system_to_stage(CoreStage::Last, interpolate_transforms)
+            .add_system_to_stage(
+                CoreStage::Last,
+                transform_propagate_system.after(interpolate_transforms),
+            );
+    }
+}
+
+/// Transforms of an entity after the last two fixed steps.
+#[derive(Component, Default)]
+pub struct Interpolated {
+    previous: Option<Transform>,
+    current: Option<Transform>,
+}
+
+fn restore_simulated_transforms(mut query: Query<(&mut Transform, &Interpolated)>) {
+    for (mut transform, interpolated) in &mut query {
+        if let Some(current) = interpolated.current {
+            *transform = current;
+        }
+    }
+}
+
+fn record_simulated_transforms(mut query: Query<(&Transform, &mut Interpolated)>) {
+    for (transform, mut interpolated) in &mut query {
+        interpolated.previous = interpolated.current.or(Some(*transform));
+        interpolated.current = Some(*transform);
+    }
+}
+
+fn interpolate_transforms(
+    clock: Res<FixedClock>,
+    mut query: Query<(&mut Transform, &Interpolated)>,
+) {
+    // further than anything moves in one step, e.g. parcels taken from the pool
+    const TELEPORT_DISTANCE: f32 = 4.0;
+
+    let overstep = clock.overstep();
+
+    for (mut transform, interpolated) in &mut query {
+        let (Some(previous), Some(current)) = (interpolated.previous, interpolated.current) else {
+            continue;
+        };
+
+        if previous.translation.distance(current.translation) > TELEPORT_DISTANCE {
+            continue;
+        }
+
+        transform.translation = previous.translation.lerp(current.translation, overstep);
+        transform.rotation = previous.rotation.slerp(current.rotation, overstep);
+        transform.scale = previous.scale.lerp(current.scale, overstep);
+    }
+}
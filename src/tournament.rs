@@ -0,0 +1,300 @@
+//! Headless rounds played by a bot across a range of seeds, summed up in tables to compare
+//! balance changes, e.g. to the difficulty presets or the parcel kinds.
+
+use std::{collections::HashMap, fmt::Write, ops::Range, path::PathBuf, sync::Mutex, thread};
+
+use crate::{
+    cli::{invalid, parse_difficulty, parse_mode, parse_number, read_level},
+    on_fixed_update,
+    prelude::*,
+    setup_headless_app, AgentServiceCode, BotStrategy, CliError, Difficulty, FixedAppExt,
+    FixedStage, GameMode, GameRng, ParcelDelivered, ParcelLost, RoundStats, Score, SpawnTimeline,
+};
+
+pub const TOURNAMENT_HELP: &str = "\
+Plays headless rounds with a bot for every seed and prints how they went.
+
+Usage: tournament [OPTIONS]
+
+Options:
+      --seeds <FIRST..END>       Seeds of the rounds, one round each [default: 0..100]
+      --bot <BOT>                greedy or stacking [default: greedy]
+      --mode <MODE>              timed, endless, zen or sudden-death [default: timed]
+      --difficulty <DIFFICULTY>  easy, normal or hard [default: normal]
+      --level <FILE>             Waves of packages in RON, like assets/waves/timeline.ron
+      --seconds <SECONDS>        Stops rounds that last longer [default: 600]
+      --threads <COUNT>          Rounds played at once [default: available cores]
+  -h, --help                     Prints this help
+";
+
+#[derive(Debug)]
+pub struct Tournament {
+    pub seeds: Range<u64>,
+    pub strategy: BotStrategy,
+    pub mode: GameMode,
+    pub difficulty: Difficulty,
+    pub level: Option<SpawnTimeline>,
+    /// Seconds after which a round is stopped.
+    pub max_seconds: f32,
+    pub threads: usize,
+}
+
+impl Default for Tournament {
+    fn default() -> Self {
+        Self {
+            seeds: 0..100,
+            strategy: BotStrategy::Greedy,
+            mode: GameMode::default(),
+            difficulty: Difficulty::default(),
+            level: None,
+            max_seconds: config::TOURNAMENT_MAX_SECONDS,
+            threads: thread::available_parallelism().map_or(1, |threads| threads.get()),
+        }
+    }
+}
+
+impl Tournament {
+    /// Parses the arguments after the program name.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, CliError> {
+        let mut tournament = Self::default();
+
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| invalid(format!("{arg} needs a value")))
+            };
+
+            match arg.as_str() {
+                "-h" | "--help" => return Err(CliError::Help),
+                "--seeds" => tournament.seeds = parse_seeds(&value()?)?,
+                "--bot" => tournament.strategy = parse_strategy(&value()?)?,
+                "--mode" => tournament.mode = parse_mode(&value()?)?,
+                "--difficulty" => tournament.difficulty = parse_difficulty(&value()?)?,
+                "--level" => tournament.level = Some(read_level(&PathBuf::from(value()?))?),
+                "--seconds" => tournament.max_seconds = parse_number(&arg, &value()?)?,
+                "--threads" => tournament.threads = parse_number(&arg, &value()?)?,
+                _ => return Err(invalid(format!("unexpected argument {arg}"))),
+            }
+        }
+
+        if !(tournament.max_seconds.is_finite() && tournament.max_seconds > 0.0) {
+            return Err(invalid("--seconds must be positive".to_string()));
+        }
+        if tournament.threads == 0 {
+            return Err(invalid("--threads must be at least 1".to_string()));
+        }
+
+        Ok(tournament)
+    }
+
+    /// Plays a round for every seed, spread over the threads, and returns them by seed.
+    pub fn run(&self) -> Vec<RoundResult> {
+        let seeds = Mutex::new(self.seeds.clone());
+        let results = Mutex::new(vec![]);
+
+        thread::scope(|scope| {
+            for _ in 0..self.threads {
+                scope.spawn(|| loop {
+                    let Some(seed) = seeds.lock().unwrap().next() else {
+                        break;
+                    };
+                    let result = self.play_round(seed);
+                    results.lock().unwrap().push(result);
+                });
+            }
+        });
+
+        let mut results = results.into_inner().unwrap();
+        results.sort_by_key(|result| result.seed);
+        results
+    }
+
+    /// Plays the round of `seed` until it's over or [`Tournament::max_seconds`] passed.
+    pub fn play_round(&self, seed: u64) -> RoundResult {
+        let mut app = App::new();
+        setup_headless_app(&mut app);
+        app.insert_resource(GameRng::from_seed(seed))
+            .insert_resource(self.mode)
+            .insert_resource(self.difficulty)
+            .init_resource::<CarrierTallies>()
+            .add_fixed_system_set_to_stage(
+                FixedStage::PostUpdate,
+                on_fixed_update(GameState::Ready).with_system(tally_carriers),
+            );
+        if let Some(level) = &self.level {
+            app.insert_resource(level.clone());
+        }
+        self.strategy.add_to(&mut app);
+
+        while *app.world.resource::<State<GameState>>().current() != GameState::GameOver
+            && app.world.resource::<RoundStats>().elapsed < self.max_seconds
+        {
+            app.update();
+        }
+
+        RoundResult {
+            seed,
+            score: app.world.resource::<Score>().score,
+            stats: app.world.resource::<RoundStats>().clone(),
+            carriers: app.world.resource::<CarrierTallies>().0.clone(),
+        }
+    }
+
+    /// Tables of the scores and deliveries of `results`.
+    pub fn report(&self, results: &[RoundResult]) -> String {
+        let mut report = format!(
+            "{} rounds of seeds {}..{}, {} bot, {} mode, {:?} difficulty\n\n",
+            results.len(),
+            self.seeds.start,
+            self.seeds.end,
+            self.strategy.name(),
+            self.mode.name(),
+            self.difficulty,
+        );
+
+        let _ = writeln!(
+            report,
+            "{:<10}{:>8}{:>8}{:>8}{:>8}{:>8}{:>8}",
+            "per round", "mean", "min", "p25", "median", "p75", "max"
+        );
+        let rows: [(&str, fn(&RoundResult) -> f32); 5] = [
+            ("score", |result| result.score as f32),
+            ("correct", |result| result.stats.correct as f32),
+            ("wrong", |result| result.stats.wrong as f32),
+            ("lost", |result| result.stats.lost as f32),
+            ("seconds", |result| result.stats.elapsed),
+        ];
+        for (name, value) in rows {
+            let distribution = Distribution::new(results.iter().map(value).collect());
+            let _ = writeln!(
+                report,
+                "{name:<10}{:>8.1}{:>8.1}{:>8.1}{:>8.1}{:>8.1}{:>8.1}",
+                distribution.mean,
+                distribution.percentile(0.0),
+                distribution.percentile(0.25),
+                distribution.percentile(0.5),
+                distribution.percentile(0.75),
+                distribution.percentile(1.0),
+            );
+        }
+
+        let _ = writeln!(
+            report,
+            "\n{:<10}{:>8}{:>8}{:>8}{:>10}",
+            "carrier", "correct", "wrong", "lost", "accuracy"
+        );
+        for code in AgentServiceCode::ALL {
+            let total = results
+                .iter()
+                .filter_map(|result| result.carriers.get(&code))
+                .fold(CarrierTally::default(), |total, tally| CarrierTally {
+                    correct: total.correct + tally.correct,
+                    wrong: total.wrong + tally.wrong,
+                    lost: total.lost + tally.lost,
+                });
+            let handled = total.correct + total.wrong + total.lost;
+            let accuracy = if handled > 0 {
+                format!("{:.0}%", 100.0 * total.correct as f32 / handled as f32)
+            } else {
+                "-".to_string()
+            };
+
+            let _ = writeln!(
+                report,
+                "{:<10}{:>8}{:>8}{:>8}{accuracy:>10}",
+                format!("{code:?}"),
+                total.correct,
+                total.wrong,
+                total.lost,
+            );
+        }
+
+        report
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct RoundResult {
+    pub seed: u64,
+    pub score: i32,
+    pub stats: RoundStats,
+    /// Deliveries and losses by the carrier of the parcel.
+    pub carriers: HashMap<AgentServiceCode, CarrierTally>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CarrierTally {
+    pub correct: u32,
+    pub wrong: u32,
+    pub lost: u32,
+}
+
+#[derive(Resource, Default)]
+struct CarrierTallies(HashMap<AgentServiceCode, CarrierTally>);
+
+fn tally_carriers(
+    mut tallies: ResMut<CarrierTallies>,
+    mut delivered: EventReader<ParcelDelivered>,
+    mut lost: EventReader<ParcelLost>,
+) {
+    for event in delivered.iter() {
+        let tally = tallies.0.entry(event.code).or_default();
+        if event.is_correct() {
+            tally.correct += 1;
+        } else {
+            tally.wrong += 1;
+        }
+    }
+
+    for event in lost.iter() {
+        tallies.0.entry(event.code).or_default().lost += 1;
+    }
+}
+
+/// Values of all rounds, sorted.
+struct Distribution {
+    values: Vec<f32>,
+    mean: f32,
+}
+
+impl Distribution {
+    fn new(mut values: Vec<f32>) -> Self {
+        values.sort_by(f32::total_cmp);
+        let mean = values.iter().sum::<f32>() / values.len().max(1) as f32;
+        Self { values, mean }
+    }
+
+    /// Nearest value at `fraction` of the sorted values, `0.0` without any.
+    fn percentile(&self, fraction: f32) -> f32 {
+        let last = self.values.len().saturating_sub(1);
+        let index = (fraction * last as f32).round() as usize;
+        self.values.get(index).copied().unwrap_or(0.0)
+    }
+}
+
+fn parse_seeds(value: &str) -> Result<Range<u64>, CliError> {
+    let seeds = value
+        .split_once("..")
+        .and_then(|(start, end)| Some(start.parse().ok()?..end.parse().ok()?))
+        .ok_or_else(|| invalid(format!("--seeds expects FIRST..END, got {value}")))?;
+
+    if seeds.is_empty() {
+        return Err(invalid(format!("--seeds {value} has no seeds")));
+    }
+
+    Ok(seeds)
+}
+
+fn parse_strategy(value: &str) -> Result<BotStrategy, CliError> {
+    BotStrategy::ALL
+        .into_iter()
+        .find(|strategy| strategy.name() == value)
+        .ok_or_else(|| {
+            let names: Vec<_> = BotStrategy::ALL.iter().map(BotStrategy::name).collect();
+            invalid(format!(
+                "unknown bot {value}, expected one of {}",
+                names.join(", ")
+            ))
+        })
+}
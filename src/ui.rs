@@ -1,11 +1,21 @@
-use crate::{prelude::*, FontAssets, Score, TimeRemaining};
+use crate::{
+    prelude::*, ActivePowerUps, Dash, DifficultyCurve, FontAssets, GameMode, Mutators,
+    ParcelSpawner, ParcelStack, Player, RoundEntity, RoundStats, Score, Stamina, TimeRemaining,
+    WaveStarted,
+};
 
 pub struct UiPlugin;
 
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
         app.add_system_set(SystemSet::on_enter(GameState::Ready).with_system(setup_ui));
-        app.add_system_set(SystemSet::on_update(GameState::Ready).with_system(update_ui));
+        app.add_system_set(
+            SystemSet::on_update(GameState::Ready)
+                .with_system(update_ui)
+                .with_system(update_power_up_ui)
+                .with_system(update_difficulty_ui)
+                .with_system(show_wave_banner),
+        );
     }
 }
 
@@ -15,7 +25,39 @@ pub struct ScoreText;
 #[derive(Component)]
 pub struct GameTime;
 
-fn setup_ui(mut commands: Commands, font_assets: Res<FontAssets>, score: Res<Score>) {
+#[derive(Component)]
+pub struct StackText;
+
+#[derive(Component)]
+pub struct PowerUpText;
+
+#[derive(Component)]
+pub struct DifficultyText;
+
+#[derive(Component)]
+pub struct MutatorsText;
+
+/// Announces heavy waves in the middle of the screen for a few seconds.
+#[derive(Component)]
+pub struct WaveBanner {
+    pub timer: Timer,
+}
+
+#[derive(Component)]
+pub struct StaminaBar;
+
+#[derive(Component)]
+pub struct DashBar;
+
+const DASH_READY_COLOR: Color = Color::rgb(0.3, 0.6, 1.0);
+const DASH_COOLDOWN_COLOR: Color = Color::rgb(0.4, 0.4, 0.4);
+
+fn setup_ui(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    score: Res<Score>,
+    mutators: Res<Mutators>,
+) {
     commands.spawn((
         TextBundle::from_section(
             format!("{:.1$}", score.score, 0),
@@ -40,6 +82,7 @@ fn setup_ui(mut commands: Commands, font_assets: Res<FontAssets>, score: Res<Sco
             ..default()
         }),
         GameTime,
+        RoundEntity,
     ));
 
     commands.spawn((
@@ -66,20 +109,264 @@ fn setup_ui(mut commands: Commands, font_assets: Res<FontAssets>, score: Res<Sco
             ..default()
         }),
         ScoreText,
+        RoundEntity,
     ));
+
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: font_assets.montserrat.clone(),
+                font_size: 30.0,
+                color: Color::rgb(1., 1., 1.),
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(5.0),
+                left: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        }),
+        StackText,
+        RoundEntity,
+    ));
+
+    commands.spawn((
+        TextBundle::default().with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(5.0),
+                right: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        }),
+        PowerUpText,
+        RoundEntity,
+    ));
+
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: font_assets.montserrat.clone(),
+                font_size: 24.0,
+                color: Color::rgb(1., 1., 1.),
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                top: Val::Px(85.0),
+                left: Val::Px(10.0),
+                ..default()
+            },
+            ..default()
+        }),
+        DifficultyText,
+        RoundEntity,
+    ));
+
+    if !mutators.0.is_empty() {
+        // mutators only change between rounds, the text is never updated
+        commands.spawn((
+            TextBundle::from_section(
+                format!(
+                    "{}  x{:.2}",
+                    mutators.describe(),
+                    mutators.score_multiplier()
+                ),
+                TextStyle {
+                    font: font_assets.montserrat.clone(),
+                    font_size: 20.0,
+                    color: Color::rgb(0.8, 0.6, 1.0),
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(115.0),
+                    left: Val::Px(10.0),
+                    ..default()
+                },
+                ..default()
+            }),
+            MutatorsText,
+            RoundEntity,
+        ));
+    }
+
+    let mut banner_timer = Timer::from_seconds(3.0, TimerMode::Once);
+    banner_timer.tick(banner_timer.duration());
+    commands.spawn((
+        TextBundle {
+            visibility: Visibility { is_visible: false },
+            ..TextBundle::from_section(
+                "BLACK FRIDAY RUSH!",
+                TextStyle {
+                    font: font_assets.montserrat.clone(),
+                    font_size: 80.0,
+                    color: Color::rgb(1.0, 0.2, 0.2),
+                },
+            )
+            .with_text_alignment(TextAlignment::CENTER)
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Percent(30.0),
+                    left: Val::Percent(28.0),
+                    ..default()
+                },
+                ..default()
+            })
+        },
+        WaveBanner {
+            timer: banner_timer,
+        },
+        RoundEntity,
+    ));
+
+    spawn_bar(&mut commands, 45.0, Color::rgb(1.0, 0.8, 0.2), StaminaBar);
+    spawn_bar(&mut commands, 65.0, DASH_READY_COLOR, DashBar);
+}
+
+/// Spawns a bar below the stack text whose fill is resized through the `marker` component.
+fn spawn_bar(commands: &mut Commands, top: f32, color: Color, marker: impl Component) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(top),
+                    left: Val::Px(10.0),
+                    ..default()
+                },
+                size: Size::new(Val::Px(150.0), Val::Px(12.0)),
+                ..default()
+            },
+            background_color: Color::rgba(0.0, 0.0, 0.0, 0.5).into(),
+            ..default()
+        })
+        .insert(RoundEntity)
+        .with_children(|parent| {
+            parent.spawn((
+                NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                        ..default()
+                    },
+                    background_color: color.into(),
+                    ..default()
+                },
+                marker,
+            ));
+        });
 }
 
 fn update_ui(
     score: Res<Score>,
     time_remaining: Res<TimeRemaining>,
+    mode: Res<GameMode>,
+    stats: Res<RoundStats>,
     mut score_text: Query<&mut Text, (With<ScoreText>, Without<GameTime>)>,
     mut game_text: Query<&mut Text, (With<GameTime>, Without<ScoreText>)>,
+    mut stack_text: Query<&mut Text, (With<StackText>, Without<ScoreText>, Without<GameTime>)>,
+    parcel_stack: Query<&ParcelStack>,
+    player: Query<(&Stamina, &Dash), With<Player>>,
+    mut stamina_bar: Query<&mut Style, (With<StaminaBar>, Without<DashBar>)>,
+    mut dash_bar: Query<(&mut Style, &mut BackgroundColor), With<DashBar>>,
 ) {
     for mut text in &mut score_text {
         text.sections[0].value = format!("Score: {:?}", score.score);
     }
 
     for mut text in &mut game_text {
-        text.sections[0].value = format!("{:.1$}", time_remaining.timer.remaining_secs(), 0);
+        text.sections[0].value = match *mode {
+            GameMode::Timed => format!("{:.1$}", time_remaining.timer.remaining_secs(), 0),
+            GameMode::Endless => format!(
+                "{:.0}  Missed {}/{}",
+                stats.elapsed,
+                stats.misses(),
+                config::ENDLESS_MAX_MISSES
+            ),
+            GameMode::Zen | GameMode::SuddenDeath => format!("{:.0}", stats.elapsed),
+        };
+    }
+
+    let stack = parcel_stack.single();
+    for mut text in &mut stack_text {
+        text.sections[0].value =
+            format!("Stack: {}/{}", stack.parcels_entries.len(), stack.capacity);
+    }
+
+    let (stamina, dash) = player.single();
+    for mut style in &mut stamina_bar {
+        style.size.width = Val::Percent(stamina.fraction() * 100.0);
+    }
+
+    for (mut style, mut color) in &mut dash_bar {
+        style.size.width = Val::Percent(dash.readiness() * 100.0);
+        *color = if dash.cooldown <= 0.0 {
+            DASH_READY_COLOR
+        } else {
+            DASH_COOLDOWN_COLOR
+        }
+        .into();
+    }
+}
+
+fn update_power_up_ui(
+    font_assets: Res<FontAssets>,
+    active: Res<ActivePowerUps>,
+    mut power_up_text: Query<&mut Text, With<PowerUpText>>,
+) {
+    for mut text in &mut power_up_text {
+        text.sections = active
+            .0
+            .iter()
+            .map(|power_up| {
+                TextSection::new(
+                    format!(
+                        "{} {:.0}\n",
+                        power_up.kind.name(),
+                        power_up.timer.remaining_secs().ceil()
+                    ),
+                    TextStyle {
+                        font: font_assets.montserrat.clone(),
+                        font_size: 30.0,
+                        color: power_up.kind.color(),
+                    },
+                )
+            })
+            .collect();
+    }
+}
+
+fn update_difficulty_ui(
+    curve: Res<DifficultyCurve>,
+    spawner: Res<ParcelSpawner>,
+    mut difficulty_text: Query<&mut Text, With<DifficultyText>>,
+) {
+    let (index, tier) = curve.tier_at(spawner.elapsed);
+    for mut text in &mut difficulty_text {
+        text.sections[0].value = format!("{} - Tier {}: {}", curve.name, index + 1, tier.name);
+    }
+}
+
+fn show_wave_banner(
+    time: Res<Time>,
+    mut waves: EventReader<WaveStarted>,
+    mut banner: Query<(&mut WaveBanner, &mut Visibility)>,
+) {
+    for (mut banner, mut visibility) in &mut banner {
+        if waves.iter().any(|wave| wave.heavy) {
+            banner.timer.reset();
+        }
+
+        banner.timer.tick(time.delta());
+        visibility.is_visible = !banner.timer.finished();
     }
 }
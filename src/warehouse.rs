@@ -1,20 +1,31 @@
 use std::time::Duration;
 
 use bevy_tweening::lens::TransformScaleLens;
+use serde::{Deserialize, Serialize};
 
-use crate::{prelude::*, ClosestParcel, Despawn, ImageAssets, ModelAssets, Parcel, Picked};
+use crate::{
+    apply_scanner, maintain_parcel_stack, on_fixed_update, prelude::*, remove_outline_from_picked,
+    ActivePowerUps, ClosestParcel, Despawn, FixedAppExt, GameMode, ImageAssets, Interpolated,
+    ModelAssets, Mutators, Parcel, Picked, PowerUpKind, RoundEntity, Thrown,
+};
 pub struct WarehousePlugin;
 
 impl Plugin for WarehousePlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<ScoreEvent>();
-        app.insert_resource(Score { score: 0 });
+        app.add_fixed_event::<ScoreEvent>();
+        app.add_fixed_event::<ParcelDelivered>();
+        app.init_resource::<Score>();
         app.add_system_set(SystemSet::on_enter(GameState::Ready).with_system(setup_ground));
-        app.add_system_set(
-            SystemSet::on_update(GameState::Ready)
-                .with_system(move_truck)
-                .with_system(collect_parcels)
-                .with_system(update_score),
+        app.add_fixed_system_set(
+            on_fixed_update(GameState::Ready)
+                .with_system(
+                    collect_parcels
+                        .after(maintain_parcel_stack)
+                        .after(remove_outline_from_picked)
+                        .after(apply_scanner),
+                )
+                .with_system(move_truck.after(collect_parcels))
+                .with_system(update_score.after(collect_parcels)),
         );
     }
 }
@@ -22,7 +33,7 @@ impl Plugin for WarehousePlugin {
 #[derive(Component)]
 pub struct Ground;
 
-#[derive(Component, PartialEq, Eq, Hash)]
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum AgentServiceCode {
     PostNord,
     DHL,
@@ -31,6 +42,13 @@ pub enum AgentServiceCode {
 }
 
 impl AgentServiceCode {
+    pub const ALL: [AgentServiceCode; 4] = [
+        AgentServiceCode::PostNord,
+        AgentServiceCode::DHL,
+        AgentServiceCode::Bring,
+        AgentServiceCode::Budbee,
+    ];
+
     pub fn color(&self) -> Color {
         match self {
             AgentServiceCode::PostNord => Color::rgb(0.0, 0.62, 0.84),
@@ -51,7 +69,7 @@ pub struct ShippingArea {
     pub truck: Entity,
 }
 
-fn setup_ground(
+pub fn setup_ground(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
@@ -59,16 +77,19 @@ fn setup_ground(
     texture_assets: Res<ImageAssets>,
 ) {
     // light
-    commands.spawn(PointLightBundle {
-        point_light: PointLight {
-            intensity: 5500.0,
-            shadows_enabled: true,
+    commands.spawn((
+        PointLightBundle {
+            point_light: PointLight {
+                intensity: 5500.0,
+                shadows_enabled: true,
+                ..default()
+            },
+            transform: Transform::from_xyz(0.0, config::PARCEL_SPAWN_Y, 0.0)
+                .looking_at(Vec3::ZERO, Vec3::Y),
             ..default()
         },
-        transform: Transform::from_xyz(0.0, config::PARCEL_SPAWN_Y, 0.0)
-            .looking_at(Vec3::ZERO, Vec3::Y),
-        ..default()
-    });
+        RoundEntity,
+    ));
 
     // ground
     commands
@@ -78,10 +99,11 @@ fn setup_ground(
                     size: config::GROUND_SIZE,
                 })),
                 material: materials.add(Color::rgb(1.0, 0.5, 0.3).into()),
-                transform: Transform::from_xyz(0.0, -0.5, 0.0),
+                transform: Transform::from_xyz(0.0, config::FLOOR_HEIGHT, 0.0),
                 ..default()
             },
             RigidBody::Fixed,
+            RoundEntity,
         ))
         .with_children(|b| {
             b.spawn((
@@ -126,6 +148,8 @@ fn setup_ground(
                     ..Default::default()
                 },
                 Truck,
+                Interpolated::default(),
+                RoundEntity,
                 Name::new("Truck".to_string()),
             ))
             .id();
@@ -165,6 +189,7 @@ fn setup_ground(
                     config::GROUND_SIZE / 2.0,
                 ),
                 Name::new("Shipping Area".to_string()),
+                RoundEntity,
             ))
             .with_children(|b| {
                 b.spawn((
@@ -182,20 +207,42 @@ fn setup_ground(
     }
 }
 
-#[derive(Resource)]
+#[derive(Resource, Default)]
 pub struct Score {
     pub score: i32,
+    /// Fraction of a point left over by the mutator multiplier, added to the next delivery.
+    pub bonus: f32,
 }
 
 pub struct ScoreEvent {
     pub score: i32,
 }
 
-fn collect_parcels(
+/// Sent when a parcel lands in a shipping area, of its own carrier or not.
+pub struct ParcelDelivered {
+    pub parcel: Entity,
+    pub code: AgentServiceCode,
+    pub area: AgentServiceCode,
+    /// Distance along the ground from where the parcel was thrown, `None` if it wasn't.
+    pub throw_distance: Option<f32>,
+}
+
+impl ParcelDelivered {
+    pub fn is_correct(&self) -> bool {
+        self.code == self.area
+    }
+}
+
+pub fn collect_parcels(
     mut commands: Commands,
     mut collisions: EventReader<CollisionEvent>,
     mut score_events: EventWriter<ScoreEvent>,
+    mut delivered_events: EventWriter<ParcelDelivered>,
+    mode: Res<GameMode>,
+    mutators: Res<Mutators>,
     mut closest_parcel: ResMut<ClosestParcel>,
+    power_ups: Res<ActivePowerUps>,
+    mut round_score: ResMut<Score>,
     mut parcels: Query<
         (
             Entity,
@@ -203,6 +250,7 @@ fn collect_parcels(
             &Collider,
             &AgentServiceCode,
             &mut OutlineVolume,
+            Option<&Thrown>,
         ),
         With<Parcel>,
     >,
@@ -235,12 +283,20 @@ fn collect_parcels(
                 continue;
             };
 
-            let (score, despawn_timer) = if (*parcel.3) != (*shipping_area.3) {
-                (-1, 600)
+            let (mut score, despawn_timer) = if (*parcel.3) != (*shipping_area.3) {
+                (if mode.has_penalties() { -1 } else { 0 }, 600)
+            } else if power_ups.is_active(PowerUpKind::DoublePoints) {
+                (2, 600)
             } else {
                 (1, 600)
             };
 
+            if score > 0 {
+                let bonus = round_score.bonus + score as f32 * mutators.score_multiplier();
+                score = bonus.trunc() as i32;
+                round_score.bonus = bonus.fract();
+            }
+
             // despawn parcel
             commands
                 .entity(parcel.0)
@@ -269,6 +325,16 @@ fn collect_parcels(
 
             // emit score event
             score_events.send(ScoreEvent { score });
+            delivered_events.send(ParcelDelivered {
+                parcel: parcel.0,
+                code: *parcel.3,
+                area: *shipping_area.3,
+                throw_distance: parcel.5.map(|thrown| {
+                    (parcel.1.translation - thrown.from)
+                        .reject_from(Vec3::Y)
+                        .length()
+                }),
+            });
 
             shipping_area.4.score += score;
             shipping_area.4.received_parcels += 1;
@@ -284,7 +350,7 @@ fn collect_parcels(
     }
 }
 
-fn update_score(mut score: ResMut<Score>, mut score_events: EventReader<ScoreEvent>) {
+pub fn update_score(mut score: ResMut<Score>, mut score_events: EventReader<ScoreEvent>) {
     for event in score_events.iter() {
         score.score += event.score;
     }
@@ -0,0 +1,252 @@
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    on_fixed_update, prelude::*, rand_parcel_linvel, rand_parcel_spawn, spawn_parcels,
+    AgentServiceCode, DifficultyCurve, FixedAppExt, GameRng, ParcelCommands, ParcelSpawner,
+    TimeScale,
+};
+
+/// Plays the scripted waves of the [`SpawnTimeline`] on top of the regular parcel spawning.
+pub struct WavesPlugin;
+
+impl Plugin for WavesPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SpawnTimeline::default());
+        app.add_event::<WaveStarted>();
+        app.add_fixed_system_set(
+            on_fixed_update(GameState::Ready).with_system(play_spawn_timeline.after(spawn_parcels)),
+        );
+    }
+}
+
+/// Waves of parcels over the course of a round, authored in `assets/waves/timeline.ron`.
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpawnTimeline {
+    /// Seconds one pass over the timeline takes.
+    pub length: f32,
+    /// Starts over from the first wave once `length` seconds have passed.
+    pub repeat: bool,
+    /// Waves ordered by the second they start at.
+    pub waves: Vec<Wave>,
+}
+
+impl SpawnTimeline {
+    /// Checks the numbers a level file could get wrong, `Err` tells which one.
+    pub fn validate(&self) -> Result<(), String> {
+        let seconds = |value: f32| value.is_finite() && value >= 0.0;
+        let positive = |value: f32| value.is_finite() && value > 0.0;
+
+        if !seconds(self.length) {
+            return Err(format!(
+                "length must be at least 0 seconds, got {}",
+                self.length
+            ));
+        }
+
+        for wave in &self.waves {
+            let name = &wave.name;
+            if !seconds(wave.at) {
+                return Err(format!(
+                    "wave {name:?} must start at least 0 seconds in, got {}",
+                    wave.at
+                ));
+            }
+
+            match wave.pattern {
+                WavePattern::Rush { interval, .. } if !positive(interval) => {
+                    return Err(format!(
+                        "wave {name:?} must rush at an interval above 0 seconds, got {interval}"
+                    ));
+                }
+                WavePattern::Calm { duration } if !seconds(duration) => {
+                    return Err(format!(
+                        "wave {name:?} must be calm for at least 0 seconds, got {duration}"
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Default for SpawnTimeline {
+    fn default() -> Self {
+        ron::from_str(include_str!("../assets/waves/timeline.ron"))
+            .expect("spawn timeline is valid")
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Wave {
+    pub name: String,
+    /// Seconds into the timeline the wave starts at.
+    pub at: f32,
+    pub pattern: WavePattern,
+    /// Announced with a "Black Friday rush" banner.
+    #[serde(default)]
+    pub heavy: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum WavePattern {
+    /// Drops `count` parcels at once all over the floor.
+    Burst { count: u32 },
+    /// Drops `count` parcels at once on a line across the floor.
+    Row {
+        count: u32,
+        #[serde(default)]
+        carrier: Option<AgentServiceCode>,
+    },
+    /// Drops `count` parcels of a single carrier one after another, a random carrier if `None`.
+    Rush {
+        #[serde(default)]
+        carrier: Option<AgentServiceCode>,
+        count: u32,
+        /// Seconds between two parcels, rejected when reading unless finite and above zero.
+        #[serde(deserialize_with = "deserialize_interval")]
+        interval: f32,
+    },
+    /// Pauses the regular spawning.
+    Calm { duration: f32 },
+}
+
+/// Reads the interval of a [`WavePattern::Rush`], whose timer can't repeat every `0` or less
+/// seconds.
+fn deserialize_interval<'de, D: serde::Deserializer<'de>>(
+    deserializer: D,
+) -> Result<f32, D::Error> {
+    let interval = f32::deserialize(deserializer)?;
+    if interval.is_finite() && interval > 0.0 {
+        Ok(interval)
+    } else {
+        Err(serde::de::Error::custom(format!(
+            "rush interval must be a positive number of seconds, got {interval}"
+        )))
+    }
+}
+
+/// Sent when a wave of the [`SpawnTimeline`] starts.
+pub struct WaveStarted {
+    pub name: String,
+    pub heavy: bool,
+}
+
+/// A [`WavePattern::Rush`] still dropping parcels.
+pub struct ActiveWave {
+    pub carrier: AgentServiceCode,
+    pub remaining: u32,
+    pub timer: Timer,
+}
+
+pub fn play_spawn_timeline(
+    time: Res<Time>,
+    time_scale: Res<TimeScale>,
+    timeline: Res<SpawnTimeline>,
+    curve: Res<DifficultyCurve>,
+    mut spawner: ResMut<ParcelSpawner>,
+    mut rng: ResMut<GameRng>,
+    mut parcels: ParcelCommands,
+    mut events: EventWriter<WaveStarted>,
+) {
+    let spawner = &mut *spawner;
+    let (_, tier) = curve.tier_at(spawner.elapsed);
+
+    if timeline.repeat
+        && spawner.next_wave >= timeline.waves.len()
+        && spawner.elapsed >= spawner.timeline_start + timeline.length
+    {
+        spawner.timeline_start += timeline.length;
+        spawner.next_wave = 0;
+    }
+
+    while let Some(wave) = timeline.waves.get(spawner.next_wave) {
+        if spawner.timeline_start + wave.at > spawner.elapsed {
+            break;
+        }
+        spawner.next_wave += 1;
+
+        events.send(WaveStarted {
+            name: wave.name.clone(),
+            heavy: wave.heavy,
+        });
+
+        match wave.pattern {
+            WavePattern::Burst { count } => {
+                for _ in 0..count {
+                    let code = tier.carrier(&mut rng.0);
+                    let kind = tier.kind(&mut rng.0);
+                    let position = rand_parcel_spawn(&mut rng.0);
+                    let linvel = rand_parcel_linvel(&mut rng.0);
+                    parcels.spawn(spawner, position, linvel, code, kind);
+                }
+            }
+            WavePattern::Row { count, carrier } => {
+                // a line through a random point, along either axis of the floor
+                let through = rand_parcel_spawn(&mut rng.0);
+                let along_x = rng.0.gen_bool(0.5);
+                let first = row_end(along_x, false);
+                let last = row_end(along_x, true);
+
+                for i in 0..count {
+                    let t = (i as f32 + 0.5) / count as f32;
+                    let mut position = first.lerp(last, t);
+                    if along_x {
+                        position.z = through.z;
+                    } else {
+                        position.x = through.x;
+                    }
+
+                    let code = carrier.unwrap_or_else(|| tier.carrier(&mut rng.0));
+                    let kind = tier.kind(&mut rng.0);
+                    parcels.spawn(spawner, position, Vec3::ZERO, code, kind);
+                }
+            }
+            WavePattern::Rush {
+                carrier,
+                count,
+                interval,
+            } => {
+                spawner.active_waves.push(ActiveWave {
+                    carrier: carrier.unwrap_or_else(|| tier.carrier(&mut rng.0)),
+                    remaining: count,
+                    timer: Timer::from_seconds(interval, TimerMode::Repeating),
+                });
+            }
+            WavePattern::Calm { duration } => {
+                spawner.calm_until = spawner.elapsed + duration;
+            }
+        }
+    }
+
+    let delta = time.delta().mul_f32(time_scale.0);
+    let mut active_waves = std::mem::take(&mut spawner.active_waves);
+    for wave in active_waves.iter_mut() {
+        wave.timer.tick(delta);
+
+        for _ in 0..wave.timer.times_finished_this_tick().min(wave.remaining) {
+            let kind = tier.kind(&mut rng.0);
+            let position = rand_parcel_spawn(&mut rng.0);
+            let linvel = rand_parcel_linvel(&mut rng.0);
+            parcels.spawn(spawner, position, linvel, wave.carrier, kind);
+            wave.remaining -= 1;
+        }
+    }
+    active_waves.retain(|wave| wave.remaining > 0);
+    spawner.active_waves = active_waves;
+}
+
+/// End of a row across the floor, the row is moved onto its line afterwards.
+fn row_end(along_x: bool, far: bool) -> Vec3 {
+    const PADDING: f32 = 1.25;
+    let edge = config::GROUND_SIZE / 2.0 - PADDING;
+    let edge = if far { edge } else { -edge };
+
+    if along_x {
+        Vec3::new(edge, config::PARCEL_SPAWN_Y, 0.0)
+    } else {
+        Vec3::new(0.0, config::PARCEL_SPAWN_Y, edge)
+    }
+}
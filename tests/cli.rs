@@ -0,0 +1,182 @@
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+use nordicnest_bf::*;
+
+/// Fixed steps of the recorded round, ended early like leaving it with Escape.
+const STEPS: u32 = 600;
+
+fn parse(args: &str) -> Result<Cli, CliError> {
+    Cli::parse(args.split_whitespace().map(String::from))
+}
+
+fn error(args: &str) -> String {
+    match parse(args) {
+        Err(CliError::Invalid(message)) => message,
+        other => panic!("{args} was accepted: {other:?}"),
+    }
+}
+
+fn temp_file(name: &str, contents: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join("bf_cli_test");
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn flags_are_parsed_and_checked() {
+    assert_eq!(parse("--help").unwrap_err(), CliError::Help);
+    assert!(CLI_HELP.contains("--replay <FILE>"));
+
+    let cli =
+        parse("--seed 7 --mode sudden-death --difficulty hard --headless --rounds 3").unwrap();
+    assert_eq!(cli.seed, Some(7));
+    assert_eq!(cli.mode, Some(GameMode::SuddenDeath));
+    assert_eq!(cli.difficulty, Some(Difficulty::Hard));
+    assert!(cli.headless);
+    assert_eq!(cli.rounds, 3);
+
+    let cli = parse("--width 800 --height 600").unwrap();
+    assert_eq!(
+        cli.window,
+        WindowOptions {
+            width: Some(800.0),
+            height: Some(600.0),
+            fullscreen: false,
+        }
+    );
+
+    assert!(error("--width 0").contains("must be positive"));
+    assert!(error("--width NaN").contains("must be positive"));
+    assert!(error("--height inf").contains("must be positive"));
+
+    assert!(error("--seed").contains("needs a value"));
+    assert!(error("--seed many").contains("expects a number"));
+    assert!(error("--mode arcade").contains("timed, endless, zen, sudden-death"));
+    assert!(error("--fast").contains("unexpected argument"));
+    assert!(error("--rounds 3").contains("needs --headless"));
+    assert!(error("--headless --rounds 0").contains("at least 1"));
+    assert!(error("--headless --fullscreen").contains("don't apply to --headless"));
+    assert!(error("--fullscreen --width 800").contains("don't apply to --fullscreen"));
+    assert!(error("--headless --rounds 2 --replay out.json").contains("single round"));
+    assert!(error("--replay missing.json").contains("could not read"));
+
+    let level = temp_file("level.ron", "(length: 60.0, repeat: false, waves: [])");
+    let cli = parse(&format!("--level {}", level.display())).unwrap();
+    assert_eq!(cli.level.unwrap().length, 60.0);
+    let broken = temp_file("broken.ron", "(length: 60.0)");
+    assert!(error(&format!("--level {}", broken.display())).contains("is not a level"));
+    for (name, wave) in [
+        (
+            "zero_interval.ron",
+            "at: 1.0, pattern: Rush(count: 3, interval: 0.0)",
+        ),
+        ("negative_start.ron", "at: -1.0, pattern: Burst(count: 3)"),
+        (
+            "negative_calm.ron",
+            "at: 1.0, pattern: Calm(duration: -2.0)",
+        ),
+    ] {
+        let level = temp_file(
+            name,
+            &format!("(length: 60.0, repeat: false, waves: [(name: \"Wave\", {wave})])"),
+        );
+        assert!(error(&format!("--level {}", level.display())).contains("is not a level"));
+    }
+}
+
+#[test]
+fn flags_take_precedence_over_the_config_file() {
+    let config = temp_file(
+        "config.ron",
+        "(seed: 3, mode: Zen, headless: true, rounds: 2)",
+    );
+
+    let cli = parse(&format!("--config {} --seed 9", config.display())).unwrap();
+    assert_eq!(cli.seed, Some(9));
+    assert_eq!(cli.mode, Some(GameMode::Zen));
+    assert!(cli.headless);
+    assert_eq!(cli.rounds, 2);
+
+    let unknown = temp_file("unknown.ron", "(speed: 2.0)");
+    assert!(error(&format!("--config {}", unknown.display())).contains("is not a config file"));
+}
+
+/// Of several headless rounds only the last one is recorded.
+#[test]
+fn the_last_of_several_rounds_is_recorded() {
+    let cli = parse("--headless --rounds 3 --record out.json").unwrap();
+
+    for round in 0..3 {
+        let mut app = App::new();
+        setup_headless_app(&mut app);
+        cli.configure(&mut app, round);
+        assert_eq!(app.world.contains_resource::<ReplayRecorder>(), round == 2);
+    }
+}
+
+/// A recorded round played back from its replay ends with the same score at the same spot.
+#[test]
+fn replays_repeat_the_recorded_round() {
+    let path = std::env::temp_dir().join("bf_cli_test_replay.json");
+    let _ = std::fs::remove_file(&path);
+
+    let recording = parse(&format!(
+        "--seed 4 --difficulty hard --headless --record {}",
+        path.display()
+    ))
+    .unwrap();
+    let mut app = App::new();
+    setup_headless_app(&mut app);
+    recording.configure(&mut app, 0);
+    for _ in 0..=STEPS {
+        app.update();
+    }
+    app.world
+        .resource_mut::<State<GameState>>()
+        .set(GameState::GameOver)
+        .unwrap();
+    app.update();
+
+    let score = app.world.resource::<Score>().score;
+    let position = app
+        .world
+        .query_filtered::<&Transform, With<Player>>()
+        .single(&app.world)
+        .translation;
+    assert!(score > 0);
+
+    let playback = parse(&format!("--headless --replay {}", path.display())).unwrap();
+    let replay = playback.replay.as_ref().unwrap();
+    assert_eq!(replay.actions.len() as u32, STEPS);
+    assert_eq!(replay.difficulty, Difficulty::Hard);
+
+    let mut app = App::new();
+    setup_headless_app(&mut app);
+    playback.configure(&mut app, 0);
+    assert_eq!(run_headless_round(&mut app), score);
+    assert_eq!(
+        app.world
+            .query_filtered::<&Transform, With<Player>>()
+            .single(&app.world)
+            .translation,
+        position
+    );
+
+    assert!(error(&format!("--replay {} --seed 1", path.display())).contains("--seed can't"));
+
+    let mut broken = replay.clone();
+    broken.timeline.waves.push(Wave {
+        name: "Too early".to_string(),
+        at: -1.0,
+        pattern: WavePattern::Burst { count: 3 },
+        heavy: false,
+    });
+    let broken = temp_file(
+        "broken_replay.json",
+        &serde_json::to_string(&broken).unwrap(),
+    );
+    assert!(error(&format!("--replay {}", broken.display())).contains("is not a replay"));
+}
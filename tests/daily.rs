@@ -0,0 +1,27 @@
+use nordicnest_bf::*;
+
+#[test]
+fn days_are_named_by_their_utc_date() {
+    assert_eq!(civil_date(0), "1970-01-01");
+    assert_eq!(civil_date(11_016), "2000-02-29");
+    assert_eq!(civil_date(19_723), "2024-01-01");
+    assert_eq!(civil_date(-1), "1969-12-31");
+}
+
+/// A day's challenge never changes, or players would compare scores of different rounds.
+#[test]
+fn challenges_are_stable() {
+    let challenge = DailyChallenge::for_day(19_723);
+    assert_eq!(
+        challenge,
+        DailyChallenge {
+            date: "2024-01-01".to_string(),
+            seed: 188_745_726_886_835_198,
+            difficulty: Difficulty::Hard,
+            mode: GameMode::Timed,
+            mutators: Mutators(vec![Mutator::GiantParcels]),
+        }
+    );
+    assert_eq!(DailyChallenge::for_day(19_723), challenge);
+    assert_ne!(DailyChallenge::for_day(19_724).seed, challenge.seed);
+}
@@ -0,0 +1,113 @@
+use std::time::Duration;
+
+use nordicnest_bf::*;
+
+/// Steps played before the round is cut short.
+const STEPS: u32 = 900;
+
+/// Center of the shipping area of `code`.
+fn area_of(code: AgentServiceCode) -> [f32; 3] {
+    let size = config::GROUND_SIZE;
+    match code {
+        AgentServiceCode::PostNord => [0.0, 0.0, -size],
+        AgentServiceCode::DHL => [size, 0.0, 0.0],
+        AgentServiceCode::Bring => [0.0, 0.0, size],
+        AgentServiceCode::Budbee => [-size, 0.0, 0.0],
+    }
+}
+
+/// Throws the top parcel to its carrier, otherwise walks to the closest resting parcel.
+fn act(observation: &EnvObservation) -> EnvAction {
+    if let Some(code) = observation.stack.last() {
+        return EnvAction {
+            throw: Some(area_of(*code)),
+            ..Default::default()
+        };
+    }
+
+    let [x, _, z] = observation.player_position;
+    let offset = |parcel: &EnvParcel| [parcel.position[0] - x, parcel.position[2] - z];
+    let resting = |parcel: &&EnvParcel| Vec3::from(parcel.velocity).length() < 0.5;
+    let closest = observation
+        .parcels
+        .iter()
+        .filter(|parcel| !parcel.stacked)
+        .filter(resting)
+        .map(offset)
+        .min_by(|a, b| (a[0].hypot(a[1])).total_cmp(&b[0].hypot(b[1])));
+    // the game picks up the closest parcel, which may be one that was just thrown
+    let in_flight_nearby = observation
+        .parcels
+        .iter()
+        .filter(|parcel| !parcel.stacked && !resting(parcel))
+        .any(|parcel| {
+            let [dx, dz] = offset(parcel);
+            dx.hypot(dz) < 4.0
+        });
+
+    EnvAction {
+        movement: closest.unwrap_or_default(),
+        pickup: closest.is_some() && !in_flight_nearby,
+        ..Default::default()
+    }
+}
+
+/// The rewards of a round add up to its score, and the round stays over once it's done.
+#[test]
+fn steps_are_rewarded_with_the_score() {
+    let mut env = WarehouseEnv::new(2);
+    let mut observation = env.reset(1);
+    assert_eq!(observation.score, 0);
+
+    let mut rewards = 0;
+    for step in 0.. {
+        if step == STEPS {
+            // leaves a second of the round
+            let mut time_remaining = env.world_mut().resource_mut::<TimeRemaining>();
+            let duration = time_remaining.timer.duration();
+            time_remaining
+                .timer
+                .set_elapsed(duration - Duration::from_secs(1));
+        }
+
+        let step = env.step(&act(&observation));
+        rewards += step.reward;
+        observation = step.observation;
+        assert_eq!(step.done, env.is_done());
+        if step.done {
+            break;
+        }
+    }
+
+    assert_eq!(rewards, observation.score);
+    assert!(rewards > 0);
+
+    let after = env.step(&act(&observation));
+    assert!(after.done);
+    assert_eq!(after.reward, 0);
+    assert_eq!(after.observation.score, observation.score);
+    assert_eq!(
+        after.observation.player_position,
+        observation.player_position
+    );
+}
+
+#[test]
+fn requests_are_read_from_json() {
+    let reset: EnvRequest = serde_json::from_str(r#"{"reset":{"seed":1}}"#).unwrap();
+    assert!(matches!(reset, EnvRequest::Reset { seed: 1 }));
+
+    let step: EnvRequest = serde_json::from_str(
+        r#"{"step":{"movement":[1.0,0.0],"pickup":true,"throw":[0.0,0.0,12.0]}}"#,
+    )
+    .unwrap();
+    let EnvRequest::Step(action) = step else {
+        panic!("expected a step, got {step:?}");
+    };
+    assert_eq!(action.movement, [1.0, 0.0]);
+    assert!(action.pickup);
+    assert_eq!(action.throw, Some([0.0, 0.0, 12.0]));
+    assert!(!action.sprint && !action.dash);
+
+    assert!(serde_json::from_str::<EnvRequest>(r#"{"jump":{}}"#).is_err());
+}
@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use nordicnest_bf::*;
+
+/// Fixed steps simulated at every frame rate.
+const STEPS: u32 = 600;
+
+fn step() -> Duration {
+    Duration::from_secs_f32(config::FIXED_TIMESTEP)
+}
+
+/// Score, parcel count and where every parcel is after [`STEPS`] steps with frames taking
+/// `frame_time`.
+fn play(frame_time: Duration) -> (i32, u64, Vec<(Entity, Vec3)>) {
+    let mut app = App::new();
+    setup_headless_app(&mut app);
+    app.insert_resource(GameRng::from_seed(7));
+    app.insert_resource(Difficulty::Hard);
+    app.insert_resource(HeadlessFrameTime(frame_time));
+
+    // the first update only starts the clock
+    for _ in 0..=(step() * STEPS).as_nanos() / frame_time.as_nanos() {
+        app.update();
+    }
+
+    let mut parcels: Vec<_> = app
+        .world
+        .query_filtered::<(Entity, &Transform), With<Parcel>>()
+        .iter(&app.world)
+        .map(|(entity, transform)| (entity, transform.translation))
+        .collect();
+    parcels.sort_by_key(|(entity, _)| *entity);
+
+    (
+        app.world.resource::<Score>().score,
+        app.world.resource::<ParcelSpawner>().count,
+        parcels,
+    )
+}
+
+/// A round plays out the same whether frames take two steps, one step or a quarter of one.
+#[test]
+fn rounds_are_independent_of_frame_rate() {
+    let at_60 = play(step());
+    assert!(at_60.1 > 0);
+
+    assert_eq!(play(step() * 2), at_60);
+    assert_eq!(play(step() / 4), at_60);
+}
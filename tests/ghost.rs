@@ -0,0 +1,59 @@
+use bevy::prelude::*;
+use nordicnest_bf::*;
+
+/// Fixed steps played by the recorded round.
+const STEPS: u32 = 120;
+
+fn start_round() -> App {
+    let mut app = App::new();
+    setup_headless_app(&mut app);
+    app.insert_resource(GameRng::from_seed(3));
+    app.add_plugin(BotPlugin(GreedyBrain));
+    app.add_plugin(GhostPlugin);
+    app.update();
+    app
+}
+
+/// The path of a finished round is kept as the best run and replayed by the ghost in the next
+/// round until it runs out.
+#[test]
+fn ghost_replays_the_best_run() {
+    let data_dir = std::env::temp_dir().join("bf_ghost_test");
+    let _ = std::fs::remove_dir_all(&data_dir);
+    std::env::set_var("BLACK_FRIDAY_DATA_DIR", &data_dir);
+
+    let mut app = start_round();
+    for _ in 0..STEPS {
+        app.update();
+    }
+    app.world
+        .resource_mut::<State<GameState>>()
+        .set(GameState::GameOver)
+        .unwrap();
+    app.update();
+
+    let run = GhostRuns::load().runs[&GameMode::Timed].clone();
+    assert_eq!(
+        run.positions.len() as u32,
+        STEPS.div_ceil(config::GHOST_SAMPLE_STEPS)
+    );
+    assert!(run.positions.first() != run.positions.last());
+
+    let mut app = start_round();
+    let mut ghost = app
+        .world
+        .query_filtered::<(&Transform, &Visibility), With<Ghost>>();
+
+    for _ in 0..STEPS + config::GHOST_SAMPLE_STEPS {
+        app.update();
+
+        let step = app.world.resource::<GhostRecording>().step - 1;
+        let (transform, visibility) = ghost.single(&app.world);
+        match run.position(step) {
+            Some(position) => assert_eq!(transform.translation, position),
+            None => assert!(!visibility.is_visible),
+        }
+    }
+
+    assert!(!ghost.single(&app.world).1.is_visible);
+}
@@ -0,0 +1,35 @@
+use bevy::prelude::*;
+use nordicnest_bf::*;
+
+/// Seconds of a Hard round simulated, long enough for dozens of parcels and a few power-ups.
+const SIMULATED_SECONDS: f32 = 45.0;
+
+/// Parcels and power-ups spawned during a round share their meshes and materials, so the
+/// asset collections don't grow with the number of spawns.
+#[test]
+fn asset_counts_stay_constant_over_a_round() {
+    let mut app = App::new();
+    setup_headless_app(&mut app);
+    app.insert_resource(GameRng::from_seed(3));
+    app.insert_resource(Difficulty::Hard);
+
+    // a couple of updates to enter the round and build the shared assets
+    for _ in 0..2 {
+        app.update();
+    }
+
+    let counts = |app: &App| {
+        (
+            app.world.resource::<Assets<Mesh>>().len(),
+            app.world.resource::<Assets<StandardMaterial>>().len(),
+        )
+    };
+    let initial = counts(&app);
+
+    for _ in 0..(SIMULATED_SECONDS / HEADLESS_TIMESTEP) as usize {
+        app.update();
+        assert_eq!(counts(&app), initial);
+    }
+
+    assert!(app.world.resource::<ParcelSpawner>().count >= 50);
+}
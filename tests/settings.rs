@@ -0,0 +1,45 @@
+use nordicnest_bf::*;
+
+#[test]
+fn settings_step_through_their_values() {
+    let mut settings = Settings::default();
+
+    Setting::Resolution.change(&mut settings, -1);
+    assert_eq!(settings.resolution, (2560.0, 1440.0));
+    Setting::Resolution.change(&mut settings, 1);
+    assert_eq!(Setting::Resolution.describe(&settings), "1280 x 720");
+
+    Setting::Vsync.change(&mut settings, -1);
+    assert!(settings.vsync);
+    assert_eq!(Setting::Vsync.describe(&settings), "On");
+
+    Setting::MasterVolume.change(&mut settings, 1);
+    assert_eq!(settings.master_volume, 1.0);
+    for _ in 0..3 {
+        Setting::MasterVolume.change(&mut settings, -1);
+    }
+    Setting::MusicVolume.change(&mut settings, -1);
+    assert_eq!(Setting::MasterVolume.describe(&settings), "70%");
+    assert!((settings.music() - 0.63).abs() < 1e-4);
+    assert_eq!(settings.sfx(), settings.master_volume);
+
+    for _ in 0..20 {
+        Setting::SfxVolume.change(&mut settings, -1);
+    }
+    assert_eq!(settings.sfx_volume, 0.0);
+}
+
+#[test]
+fn settings_files_keep_the_defaults_of_missing_options() {
+    let settings: Settings =
+        serde_json::from_str(r#"{ "fullscreen": true, "camera_shake": false }"#).unwrap();
+
+    assert_eq!(
+        settings,
+        Settings {
+            fullscreen: true,
+            camera_shake: false,
+            ..Settings::default()
+        }
+    );
+}
@@ -0,0 +1,104 @@
+use bevy::prelude::*;
+use nordicnest_bf::*;
+
+/// Updates played at most before the snapshot is taken, while waiting for a stacked parcel.
+const MAX_UPDATES: u32 = 3000;
+/// Updates the snapshot's round and the restored one play on for before they're compared.
+const UPDATES_AFTER: u32 = 600;
+
+fn start_round(seed: u64, mode: GameMode, difficulty: Difficulty, mutators: Mutators) -> App {
+    let mut app = App::new();
+    setup_headless_app(&mut app);
+    app.insert_resource(GameRng::from_seed(seed));
+    app.insert_resource(mode);
+    app.insert_resource(difficulty);
+    app.insert_resource(mutators);
+    app.add_plugin(BotPlugin(GreedyBrain));
+    app.update();
+    app
+}
+
+/// A snapshot saved mid-round and restored into another round of other rules is taken again
+/// unchanged, and the restored round plays on by the rules of the snapshot exactly like the
+/// round it was taken of.
+#[test]
+fn snapshots_restore_the_round() {
+    std::env::set_var(
+        "BLACK_FRIDAY_DATA_DIR",
+        std::env::temp_dir().join("bf_snapshot_test"),
+    );
+
+    let mut app = start_round(
+        5,
+        GameMode::Timed,
+        Difficulty::Hard,
+        Mutators(vec![Mutator::LowGravity, Mutator::DoubleSpawnRate]),
+    );
+    let mut updates = 0;
+    while updates < MAX_UPDATES
+        && (app.world.resource::<Score>().score <= 0
+            || app
+                .world
+                .query::<&ParcelStack>()
+                .single(&app.world)
+                .parcels_entries
+                .is_empty())
+    {
+        app.update();
+        updates += 1;
+    }
+
+    let rng = app.world.resource::<GameRng>().clone();
+    let snapshot = Snapshot::capture(&mut app.world);
+    assert_eq!(*app.world.resource::<GameRng>(), rng);
+    assert!(snapshot.parcels.iter().any(|p| p.stacked));
+    assert!(snapshot.score > 0);
+
+    let mut stale = snapshot.clone();
+    stale.version += 1;
+    stale.save();
+    assert_eq!(Snapshot::load(), None);
+
+    snapshot.save();
+    let loaded = Snapshot::load().unwrap();
+    assert_eq!(loaded, snapshot);
+
+    let mut other = start_round(
+        6,
+        GameMode::Zen,
+        Difficulty::Easy,
+        Mutators(vec![Mutator::GiantParcels]),
+    );
+    loaded.restore(&mut other.world);
+
+    assert_eq!(*other.world.resource::<GameMode>(), GameMode::Timed);
+    assert_eq!(*other.world.resource::<Difficulty>(), Difficulty::Hard);
+    assert_eq!(
+        other.world.resource::<RapierConfiguration>().gravity,
+        app.world.resource::<RapierConfiguration>().gravity
+    );
+    // the parcels are built for the mutators of the snapshot
+    for (collider, kind) in other
+        .world
+        .query_filtered::<(&Collider, &ParcelKind), With<Parcel>>()
+        .iter(&other.world)
+    {
+        let half_size = parcel_size(*kind, &snapshot.mutators) / 2.0;
+        assert_eq!(
+            collider.as_cuboid().unwrap().half_extents(),
+            Vec3::splat(half_size)
+        );
+    }
+
+    let restored = Snapshot::capture(&mut other.world);
+    assert_eq!(restored, snapshot);
+
+    for _ in 0..UPDATES_AFTER {
+        app.update();
+        other.update();
+    }
+
+    let played = Snapshot::capture(&mut app.world);
+    assert!(played.spawner.count > snapshot.spawner.count);
+    assert_eq!(Snapshot::capture(&mut other.world), played);
+}
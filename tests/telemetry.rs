@@ -0,0 +1,67 @@
+#![cfg(feature = "telemetry")]
+
+use bevy::prelude::*;
+use nordicnest_bf::*;
+
+/// A round played by a bot logs its parcels and score, and the log is written as JSON lines
+/// once the round is over.
+#[test]
+fn telemetry_logs_the_round() {
+    let data_dir = std::env::temp_dir().join("bf_telemetry_test");
+    let _ = std::fs::remove_dir_all(&data_dir);
+    std::env::set_var("BLACK_FRIDAY_DATA_DIR", &data_dir);
+
+    let mut app = App::new();
+    setup_headless_app(&mut app);
+    app.insert_resource(GameRng::from_seed(2));
+    app.add_plugin(BotPlugin(GreedyBrain));
+
+    while app.world.resource::<Score>().score < 3 {
+        app.update();
+    }
+
+    let telemetry = app.world.resource::<Telemetry>();
+    let logged = |matches: fn(&TelemetryEvent) -> bool| {
+        telemetry
+            .records
+            .iter()
+            .any(|record| matches(&record.event))
+    };
+    assert!(logged(|event| matches!(
+        event,
+        TelemetryEvent::ParcelSpawned { .. }
+    )));
+    assert!(logged(|event| matches!(
+        event,
+        TelemetryEvent::ParcelPickedUp { .. }
+    )));
+    assert!(logged(|event| matches!(
+        event,
+        TelemetryEvent::ParcelDelivered { time_alive, .. } if *time_alive > 0.0
+    )));
+    assert!(logged(|event| matches!(
+        event,
+        TelemetryEvent::ScoreChanged { .. }
+    )));
+    assert!(telemetry
+        .records
+        .windows(2)
+        .all(|pair| pair[0].time <= pair[1].time));
+    let lines = telemetry.to_json_lines();
+
+    app.world
+        .resource_mut::<State<GameState>>()
+        .set(GameState::GameOver)
+        .unwrap();
+    app.update();
+
+    let files: Vec<_> = std::fs::read_dir(data_dir.join("telemetry"))
+        .unwrap()
+        .collect();
+    assert_eq!(files.len(), 1);
+    let written = std::fs::read_to_string(files[0].as_ref().unwrap().path()).unwrap();
+    assert_eq!(written, lines);
+
+    let first: serde_json::Value = serde_json::from_str(written.lines().next().unwrap()).unwrap();
+    assert_eq!(first["event"], "parcel_spawned");
+}
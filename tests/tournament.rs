@@ -0,0 +1,63 @@
+use nordicnest_bf::*;
+
+fn parse(args: &str) -> Result<Tournament, CliError> {
+    Tournament::parse(args.split_whitespace().map(String::from))
+}
+
+#[test]
+fn tournament_flags_are_parsed_and_checked() {
+    let tournament =
+        parse("--seeds 5..9 --bot stacking --mode endless --difficulty easy --threads 3").unwrap();
+    assert_eq!(tournament.seeds, 5..9);
+    assert_eq!(tournament.strategy, BotStrategy::Stacking);
+    assert_eq!(tournament.mode, GameMode::Endless);
+    assert_eq!(tournament.difficulty, Difficulty::Easy);
+    assert_eq!(tournament.threads, 3);
+
+    let invalid = |args: &str| match parse(args) {
+        Err(CliError::Invalid(message)) => message,
+        other => panic!("{args} was accepted: {other:?}"),
+    };
+    assert!(invalid("--seeds 9").contains("FIRST..END"));
+    assert!(invalid("--seeds 9..9").contains("no seeds"));
+    assert!(invalid("--bot random").contains("greedy, stacking"));
+    assert!(invalid("--threads 0").contains("at least 1"));
+    assert!(invalid("--seconds 0").contains("positive"));
+    assert!(invalid("--seconds NaN").contains("positive"));
+    assert!(invalid("--seconds inf").contains("positive"));
+}
+
+/// Rounds played side by side come out the same as played one by one, and add up in the report.
+#[test]
+fn tournaments_sum_up_their_rounds() {
+    let tournament = parse("--seeds 3..6 --difficulty hard --seconds 10 --threads 2").unwrap();
+
+    let results = tournament.run();
+    assert_eq!(
+        results.iter().map(|result| result.seed).collect::<Vec<_>>(),
+        [3, 4, 5]
+    );
+
+    let alone = tournament.play_round(4);
+    assert_eq!(alone.score, results[1].score);
+    assert_eq!(alone.carriers, results[1].carriers);
+
+    for result in &results {
+        assert!(result.stats.elapsed >= 10.0);
+        let delivered: u32 = result
+            .carriers
+            .values()
+            .map(|tally| tally.correct + tally.wrong)
+            .sum();
+        assert_eq!(delivered, result.stats.correct + result.stats.wrong);
+    }
+    assert!(results.iter().any(|result| result.score > 0));
+
+    let report = tournament.report(&results);
+    assert!(report.starts_with("3 rounds of seeds 3..6, greedy bot"));
+    for row in [
+        "score", "correct", "wrong", "lost", "PostNord", "DHL", "Bring", "Budbee",
+    ] {
+        assert!(report.lines().any(|line| line.starts_with(row)), "{report}");
+    }
+}
@@ -0,0 +1,16 @@
+use nordicnest_bf::*;
+
+#[test]
+fn rush_intervals_are_checked_when_read() {
+    let rush = |interval: &str| {
+        ron::from_str::<WavePattern>(&format!("Rush(count: 3, interval: {interval})"))
+    };
+
+    assert!(matches!(
+        rush("0.5"),
+        Ok(WavePattern::Rush { count: 3, interval, .. }) if interval == 0.5
+    ));
+    for interval in ["0.0", "-1.0", "inf", "NaN"] {
+        assert!(rush(interval).is_err(), "{interval} was accepted");
+    }
+}